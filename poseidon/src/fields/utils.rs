@@ -1,6 +1,5 @@
 use ark_ff::PrimeField;
 // use rand::{thread_rng, Rng};
-// use sha3::digest::XofReader;
 // use std::cmp::min;
 // use core::num::ParseIntError;
 use crate::ark_ff::BigInteger;
@@ -10,10 +9,106 @@ use hex::FromHex;
 use cbor4ii::serde::{from_slice, to_vec};
 use data_encoding::BASE64;
 use serde::de::DeserializeOwned;
+use sha3::digest::XofReader;
+use std::fmt;
+
+/// Error returned when decoding a field element from untrusted input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FieldDecodeError {
+    MissingPrefix,
+    OddLength,
+    InvalidHex,
+    NonCanonical,
+    InvalidBase64,
+    InvalidCbor,
+    WrongLength { expected: usize, found: usize },
+}
+
+impl fmt::Display for FieldDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldDecodeError::MissingPrefix => f.write_str("Missing 0x prefix in hex string"),
+            FieldDecodeError::OddLength => f.write_str("Odd number of digits in hex string"),
+            FieldDecodeError::InvalidHex => f.write_str("Invalid character in hex string"),
+            FieldDecodeError::NonCanonical => f.write_str("Value is not below the field modulus"),
+            FieldDecodeError::InvalidBase64 => f.write_str("Invalid Base64 string"),
+            FieldDecodeError::InvalidCbor => f.write_str("Invalid CBOR encoding"),
+            FieldDecodeError::WrongLength { expected, found } => write!(
+                f,
+                "Expected {} bytes per field element, found {}",
+                expected, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FieldDecodeError {}
 
 pub fn from_hex<F: PrimeField>(s: &str) -> F {
-    let a = Vec::from_hex(&s[2..]).expect("Invalid Hex String");
-    F::from_be_bytes_mod_order(&a as &[u8])
+    try_from_hex(s).expect("Invalid Hex String")
+}
+
+pub fn try_from_hex<F: PrimeField>(s: &str) -> Result<F, FieldDecodeError> {
+    try_from_be_bytes(&parse_hex(s)?)
+}
+
+pub fn from_hex_strict<F: PrimeField>(s: &str) -> F {
+    try_from_hex_strict(s).expect("Invalid Hex String")
+}
+
+// Like try_from_hex, but also requires exactly field_byte_len::<F>() bytes
+pub fn try_from_hex_strict<F: PrimeField>(s: &str) -> Result<F, FieldDecodeError> {
+    try_from_be_bytes_strict(&parse_hex(s)?)
+}
+
+// Bytes of a 0x-prefixed hex string with a non-empty, even number of digits
+fn parse_hex(s: &str) -> Result<Vec<u8>, FieldDecodeError> {
+    let digits = s.strip_prefix("0x").ok_or(FieldDecodeError::MissingPrefix)?;
+    if digits.is_empty() {
+        return Err(FieldDecodeError::InvalidHex);
+    }
+    if digits.len() % 2 != 0 {
+        return Err(FieldDecodeError::OddLength);
+    }
+    Vec::from_hex(digits).map_err(|_| FieldDecodeError::InvalidHex)
+}
+
+// Number of bytes of a canonically encoded field element
+pub fn field_byte_len<F: PrimeField>() -> usize {
+    (F::MODULUS_BIT_SIZE as usize).div_ceil(8)
+}
+
+// Canonical big-endian bytes of a field element, field_byte_len::<F>() long
+pub fn to_be_bytes<F: PrimeField>(element: &F) -> Vec<u8> {
+    let bytes = element.into_bigint().to_bytes_be(); // Convert the BigInteger to a byte array in big-endian
+    bytes[bytes.len() - field_byte_len::<F>()..].to_vec() // Drop the zero bytes of unused limbs
+}
+
+// Like try_from_be_bytes, but also requires exactly field_byte_len::<F>() bytes
+pub fn try_from_be_bytes_strict<F: PrimeField>(bytes: &[u8]) -> Result<F, FieldDecodeError> {
+    let expected = field_byte_len::<F>();
+    if bytes.len() != expected {
+        return Err(FieldDecodeError::WrongLength {
+            expected,
+            found: bytes.len(),
+        });
+    }
+    try_from_be_bytes(bytes)
+}
+
+// Big-endian bytes to field element, rejecting values >= modulus instead of reducing them
+pub fn try_from_be_bytes<F: PrimeField>(bytes: &[u8]) -> Result<F, FieldDecodeError> {
+    let element = F::from_be_bytes_mod_order(bytes);
+    let canonical = element.into_bigint().to_bytes_be();
+    if strip_leading_zeros(bytes) != strip_leading_zeros(&canonical) {
+        return Err(FieldDecodeError::NonCanonical);
+    }
+    Ok(element)
+}
+
+fn strip_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    &bytes[start..]
 }
 
 pub fn random_scalar<F: PrimeField>() -> F {
@@ -30,17 +125,51 @@ pub fn random_scalar_without_0<F: PrimeField>() -> F {
     }
 }
 
+// Samples field elements from an XOF by rejection: reads field_byte_len::<F>() bytes as a
+// little-endian integer, masks the bits above MODULUS_BIT_SIZE and retries on values >= p
+pub fn field_element_from_shake<F: PrimeField>(reader: &mut dyn XofReader) -> F {
+    let bytes = field_byte_len::<F>();
+    let mod_ = F::MODULUS_BIT_SIZE % 8;
+    let mask = if mod_ == 0 { 0xFF } else { (1u8 << mod_) - 1 };
+    let mut buf = vec![0u8; bytes];
+
+    loop {
+        reader.read(&mut buf);
+        buf[bytes - 1] &= mask;
+        let bits: Vec<bool> = buf
+            .iter()
+            .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+            .collect();
+        if let Some(element) = F::from_bigint(F::BigInt::from_bits_le(&bits)) {
+            return element;
+        }
+    }
+}
+
+pub fn field_element_from_shake_without_0<F: PrimeField>(reader: &mut dyn XofReader) -> F {
+    loop {
+        let element = field_element_from_shake::<F>(reader);
+        if !element.is_zero() {
+            return element;
+        }
+    }
+}
+
+// Each element is written as field_byte_len::<F>() big-endian bytes, the width the strict
+// decoders expect: 32 bytes for BN256, 8 for Goldilocks and 4 for BabyBear.
+// Breaking format change: BabyBear elements are 4 bytes wide, not the 8 bytes of a u64 limb,
+// so the output no longer matches strings written at the old width.
 pub fn to_hex<F: PrimeField>(field_elements: &[F]) -> Vec<String> {
     field_elements
         .iter()
         .map(|element| {
-            let big_int = element.into_bigint(); // Convert the element to its BigInteger representation
-            let bytes = big_int.to_bytes_be(); // Convert the BigInteger to a byte array in big-endian
+            let bytes = to_be_bytes(element); // Canonical big-endian bytes of the element
             format!("0x{}", hex::encode(bytes)) // Prepend '0x' to the hexadecimal string
         })
         .collect() // Collect the converted strings into a Vec
 }
 
+// Byte strings of field_byte_len::<F>() bytes, the same width as to_hex (breaking for BabyBear)
 pub fn encode_to_cbor_string<F: PrimeField>(field_elements: &[F]) -> String {
     // TODO: cbor size opt
     // let elements: Vec<<F as PrimeField>::BigInt> = field_elements
@@ -52,12 +181,7 @@ pub fn encode_to_cbor_string<F: PrimeField>(field_elements: &[F]) -> String {
     //     .collect();
     let elements: Vec<Vec<u8>> = field_elements
         .iter()
-        .map(|element| {
-            let big_int: <F as PrimeField>::BigInt = element.into_bigint(); // Convert the element to its BigInteger representation
-            let bytes = big_int.to_bytes_be(); // Convert the BigInteger to a byte array in big-endian
-
-            bytes
-        })
+        .map(to_be_bytes) // Canonical big-endian bytes of each element
         .collect();
 
     let buf = to_vec(Vec::new(), &elements).expect("CBOR encoding failed");
@@ -90,21 +214,217 @@ pub fn encode_to_cbor_string_slim<F: PrimeField>(field_elements: &[F]) -> String
 // g5ggCxi2GB0YJBjaGMoYVRjuGLwYsRiSGJoYghhlDxgyGIEYNBgzGE0YqRiOGKQY+BhHGPcYYAUYTxhKGDAYM5ggGDAYOxhvGHwYhhjQGEMYvxjLGMwYgBghGE8YJhijAhh3GKEYXRg/GHQYyhhlGEkYkhjeGP4Yfxj4GNAYNRhwmCAYHhjSGFEYlBhUGCsSGO4Y+BhhGHMYYRjDGLoYfBhSGOYYYBixGEUYmRhEGCcYzBiGGCkYYhhCGM8YdhhuGMg="
 
 pub fn decode_from_cbor_string<F: PrimeField + DeserializeOwned>(encoded_str: &str) -> Vec<F> {
+    try_decode_from_cbor_string(encoded_str).expect("Failed to decode CBOR string")
+}
+
+pub fn try_decode_from_cbor_string<F: PrimeField>(
+    encoded_str: &str,
+) -> Result<Vec<F>, FieldDecodeError> {
+    decode_cbor_byte_arrays(encoded_str)?
+        .into_iter()
+        .map(|bytes| try_from_be_bytes(&bytes))
+        .collect()
+}
+
+// Like try_decode_from_cbor_string, but also requires exactly field_byte_len::<F>() bytes per element
+pub fn try_decode_from_cbor_string_strict<F: PrimeField>(
+    encoded_str: &str,
+) -> Result<Vec<F>, FieldDecodeError> {
+    decode_cbor_byte_arrays(encoded_str)?
+        .into_iter()
+        .map(|bytes| try_from_be_bytes_strict(&bytes))
+        .collect()
+}
+
+fn decode_cbor_byte_arrays(encoded_str: &str) -> Result<Vec<Vec<u8>>, FieldDecodeError> {
     // Decode the Base64 string into a CBOR byte sequence
     let cbor_bytes = BASE64
         .decode(encoded_str.as_bytes())
-        .expect("Failed to decode Base64");
+        .map_err(|_| FieldDecodeError::InvalidBase64)?;
 
     // Deserialize the CBOR byte sequence into Vec<Vec<u8>>
-    let byte_arrays: Vec<Vec<u8>> = from_slice(&cbor_bytes).expect("Failed to decode CBOR");
+    from_slice(&cbor_bytes).map_err(|_| FieldDecodeError::InvalidCbor)
+}
 
-    // Convert each byte array back to a PrimeField element
-    byte_arrays
-        .into_iter()
-        .map(|bytes| F::from_be_bytes_mod_order(&bytes))
+// Number of bytes packed into one field element, small enough to stay below the modulus
+pub fn bytes_chunk_len<F: PrimeField>() -> usize {
+    (F::MODULUS_BIT_SIZE as usize - 1) / 8
+}
+
+// Injective packing of a byte string into field elements. The string is prefixed with its
+// length as 8 big-endian bytes, split into chunks of bytes_chunk_len::<F>() bytes, the last
+// one padded with zeros, and every chunk is read as a big-endian integer.
+pub fn pack_bytes<F: PrimeField>(bytes: &[u8]) -> Vec<F> {
+    let chunk_len = bytes_chunk_len::<F>();
+    let mut padded = (bytes.len() as u64).to_be_bytes().to_vec();
+    padded.extend_from_slice(bytes);
+    padded.resize(padded.len().div_ceil(chunk_len) * chunk_len, 0);
+
+    padded
+        .chunks(chunk_len)
+        .map(F::from_be_bytes_mod_order)
         .collect()
 }
 
+#[cfg(test)]
+mod utils_tests {
+    use super::*;
+    use crate::fields::{babybear::FpBabyBear, bn256::FpBN256, goldilocks::FpGoldiLocks};
+
+    #[test]
+    fn hex_roundtrip() {
+        let elements: Vec<FpBN256> = (0..4).map(|_| random_scalar()).collect();
+        for (element, s) in elements.iter().zip(to_hex(&elements)) {
+            assert_eq!(try_from_hex::<FpBN256>(&s), Ok(*element));
+        }
+    }
+
+    #[test]
+    fn hex_errors() {
+        type Scalar = FpGoldiLocks;
+        assert_eq!(try_from_hex::<Scalar>("0x0a"), Ok(Scalar::from(10u64)));
+        assert_eq!(try_from_hex::<Scalar>("0a"), Err(FieldDecodeError::MissingPrefix));
+        assert_eq!(try_from_hex::<Scalar>(""), Err(FieldDecodeError::MissingPrefix));
+        assert_eq!(try_from_hex::<Scalar>("0xa"), Err(FieldDecodeError::OddLength));
+        assert_eq!(try_from_hex::<Scalar>("0xzz"), Err(FieldDecodeError::InvalidHex));
+        assert_eq!(try_from_hex::<Scalar>("0x"), Err(FieldDecodeError::InvalidHex));
+        assert_eq!(try_from_hex_strict::<Scalar>("0x"), Err(FieldDecodeError::InvalidHex));
+        // modulus and modulus + 1
+        assert_eq!(
+            try_from_hex::<Scalar>("0xffffffff00000001"),
+            Err(FieldDecodeError::NonCanonical)
+        );
+        assert_eq!(
+            try_from_hex::<Scalar>("0x00ffffffff00000002"),
+            Err(FieldDecodeError::NonCanonical)
+        );
+        assert_eq!(
+            try_from_hex::<Scalar>("0x00ffffffff00000000"),
+            Ok(-Scalar::from(1u64))
+        );
+    }
+
+    #[test]
+    fn strict_lengths() {
+        assert_eq!(field_byte_len::<FpBN256>(), 32);
+        assert_eq!(field_byte_len::<FpGoldiLocks>(), 8);
+        assert_eq!(field_byte_len::<FpBabyBear>(), 4);
+
+        let elements: Vec<FpBabyBear> = (0..4).map(|_| random_scalar()).collect();
+        for (element, s) in elements.iter().zip(to_hex(&elements)) {
+            assert_eq!(s.len(), 2 + 2 * 4);
+            assert_eq!(try_from_hex_strict::<FpBabyBear>(&s), Ok(*element));
+        }
+        let encoded = encode_to_cbor_string(&elements);
+        assert_eq!(try_decode_from_cbor_string_strict(&encoded), Ok(elements));
+
+        // the same value with a different length is accepted leniently only
+        let one = FpBabyBear::from(1u64);
+        assert_eq!(try_from_hex::<FpBabyBear>("0x0000000000000001"), Ok(one));
+        assert_eq!(
+            try_from_hex_strict::<FpBabyBear>("0x0000000000000001"),
+            Err(FieldDecodeError::WrongLength {
+                expected: 4,
+                found: 8
+            })
+        );
+        assert_eq!(
+            try_from_hex_strict::<FpBabyBear>("0x01"),
+            Err(FieldDecodeError::WrongLength {
+                expected: 4,
+                found: 1
+            })
+        );
+        assert_eq!(
+            try_from_hex_strict::<FpBabyBear>("0x78000001"),
+            Err(FieldDecodeError::NonCanonical)
+        );
+
+        let padded = vec![vec![0u8; 33]];
+        let buf = to_vec(Vec::new(), &padded).unwrap();
+        assert_eq!(
+            try_decode_from_cbor_string_strict::<FpBN256>(&BASE64.encode(&buf)),
+            Err(FieldDecodeError::WrongLength {
+                expected: 32,
+                found: 33
+            })
+        );
+        assert_eq!(
+            try_decode_from_cbor_string::<FpBN256>(&BASE64.encode(&buf)),
+            Ok(vec![FpBN256::from(0u64)])
+        );
+    }
+
+    #[test]
+    fn legacy_width() {
+        // 12345 as written before elements were trimmed to field_byte_len bytes
+        let element = FpBabyBear::from(12345u64);
+        let legacy_hex = "0x0000000000003039";
+        let legacy_cbor = "gYgAAAAAAAAYMBg5";
+
+        assert_eq!(try_from_hex::<FpBabyBear>(legacy_hex), Ok(element));
+        assert_eq!(to_hex(&[element]), vec!["0x00003039".to_string()]);
+        assert_eq!(
+            try_decode_from_cbor_string::<FpBabyBear>(legacy_cbor),
+            Ok(vec![element])
+        );
+        assert_eq!(encode_to_cbor_string(&[element]), "gYQAABgwGDk=");
+        assert_eq!(
+            try_decode_from_cbor_string_strict::<FpBabyBear>(legacy_cbor),
+            Err(FieldDecodeError::WrongLength {
+                expected: 4,
+                found: 8
+            })
+        );
+    }
+
+    #[test]
+    fn cbor_errors() {
+        let elements: Vec<FpGoldiLocks> = (0..4).map(|_| random_scalar()).collect();
+        let encoded = encode_to_cbor_string(&elements);
+        assert_eq!(try_decode_from_cbor_string(&encoded), Ok(elements));
+
+        assert_eq!(
+            try_decode_from_cbor_string::<FpGoldiLocks>("not base64!"),
+            Err(FieldDecodeError::InvalidBase64)
+        );
+        assert_eq!(
+            try_decode_from_cbor_string::<FpGoldiLocks>(&BASE64.encode(&[0xff, 0x00])),
+            Err(FieldDecodeError::InvalidCbor)
+        );
+
+        let modulus = vec![vec![0xffu8, 0xff, 0xff, 0xff, 0, 0, 0, 1]];
+        let buf = to_vec(Vec::new(), &modulus).unwrap();
+        assert_eq!(
+            try_decode_from_cbor_string::<FpGoldiLocks>(&BASE64.encode(&buf)),
+            Err(FieldDecodeError::NonCanonical)
+        );
+    }
+
+    #[test]
+    fn pack_bytes_injective() {
+        // BabyBear takes 3 bytes per element, Goldilocks 7
+        assert_eq!(bytes_chunk_len::<FpBabyBear>(), 3);
+        assert_eq!(bytes_chunk_len::<FpGoldiLocks>(), 7);
+        assert_eq!(bytes_chunk_len::<FpBN256>(), 31);
+
+        let inputs: [&[u8]; 5] = [&[], &[0], &[0, 0], &[1], &[0, 1]];
+        for (i, a) in inputs.iter().enumerate() {
+            for b in inputs[i + 1..].iter() {
+                assert_ne!(pack_bytes::<FpBabyBear>(a), pack_bytes::<FpBabyBear>(b));
+                assert_ne!(pack_bytes::<FpGoldiLocks>(a), pack_bytes::<FpGoldiLocks>(b));
+            }
+        }
+
+        // all ones chunks stay below the modulus, so no reduction happens
+        let packed = pack_bytes::<FpBabyBear>(&[0xff; 6]);
+        assert_eq!(packed.len(), 5);
+        assert_eq!(packed[2], FpBabyBear::from(0x00_06ffu64));
+        assert_eq!(packed[3], FpBabyBear::from(0xff_ffffu64));
+        assert_eq!(packed[4], FpBabyBear::from(0xff_ff00u64));
+    }
+}
+
 //-----------------------------------------------------------------------------
 // pub fn from_u64<F: PrimeField>(val: u64) -> F {
 //     F::from_repr(F::Repr::from(val)).unwrap()
@@ -143,42 +463,3 @@ pub fn decode_from_cbor_string<F: PrimeField + DeserializeOwned>(encoded_str: &s
 //     tmp.as_mut().copy_from_slice(repr);
 //     F::from_repr(tmp)
 // }
-
-// pub fn field_element_from_shake<F: PrimeField>(reader: &mut dyn XofReader) -> F {
-//     let bytes = f64::ceil(F::MODULUS_BIT_SIZE as f64 / 8f64) as usize;
-//     let mut words = f64::ceil(bytes as f64 / 8f64) as usize;
-//     if F::MODULUS_BIT_SIZE == 64 { // Quick and dirty fix for Goldilocks
-//         words = 2;
-//     }
-
-//     let mod_ = F::NUM_BITS % 8;
-//     let mask = if mod_ == 0 { 0xFF } else { (1u8 << mod_) - 1 };
-//     let mut buf = vec![0u8; bytes];
-//     let mut word_buf = vec![0u64; words];
-
-//     let len = buf.len();
-//     loop {
-//         reader.read(&mut buf);
-//         buf[len - 1] &= mask;
-//         for i in 0..words {
-//             let mut byte_array = [0u8; 8];
-//             for j in i * 8..min((i + 1) * 8, len) {
-//                 byte_array[j - i * 8] = buf[j];
-//             }
-//             word_buf[i] = u64::from_le_bytes(byte_array);
-//         }
-//         let res = from_limbs_with_error::<F>(&word_buf);
-//         if let Ok(el) = res {
-//             return el;
-//         }
-//     }
-// }
-
-// pub fn field_element_from_shake_without_0<F: PrimeField>(reader: &mut dyn XofReader) -> F {
-//     loop {
-//         let element = field_element_from_shake::<F>(reader);
-//         if !element.is_zero() {
-//             return element;
-//         }
-//     }
-// }
@@ -1,4 +1,5 @@
 use crate::merkle_tree::merkle_tree_fp::MerkleTreeHash;
+use crate::permutation::{Permutation, PermutationKind};
 
 use std::sync::Arc;
 
@@ -47,9 +48,7 @@ impl<S: PrimeField> Gmimc<S> {
                 out.mul_assign(&input);
                 out
             }
-            _ => {
-                panic!();
-            }
+            d => input.pow([d as u64]),
         }
     }
 
@@ -113,6 +112,20 @@ impl<S: PrimeField> Gmimc<S> {
     }
 }
 
+impl<F: PrimeField> Permutation<F> for Gmimc<F> {
+    fn get_t(&self) -> usize {
+        Gmimc::get_t(self)
+    }
+
+    fn permutation(&self, input: &[F]) -> Vec<F> {
+        Gmimc::permutation(self, input)
+    }
+
+    fn kind(&self) -> PermutationKind {
+        PermutationKind::Gmimc
+    }
+}
+
 impl<F: PrimeField> MerkleTreeHash<F> for Gmimc<F> {
     fn compress(&self, input: &[&F]) -> F {
         self.permutation(&[input[0].to_owned(), input[1].to_owned(), F::zero()])[0]
@@ -170,7 +183,8 @@ mod gmimc_tests_bls12 {
 mod gmimc_tests_bn256 {
     use super::*;
     use crate::gmimc::gmimc_instance_bn256::GMIMC_BN_3_PARAMS;
-    use crate::fields::{bn256::FpBN256, utils::random_scalar};
+    use crate::fields::{bn256::FpBN256, utils::from_hex, utils::random_scalar};
+    use crate::utils::ParamsError;
 
     type Scalar = FpBN256;
 
@@ -211,12 +225,47 @@ mod gmimc_tests_bn256 {
             assert_eq!(perm1, perm2);
         }
     }
+
+    #[test]
+    fn kats() {
+        // round constants are derived from SHAKE128, so they are stable across runs
+        assert_eq!(
+            GMIMC_BN_3_PARAMS.round_constants[0],
+            from_hex("0x0f5842ee542823d4f8d1dcf66c4ba0b1c1793ceaa7feebe1e85f5613dcd1d100")
+        );
+        let gmimc = Gmimc::new(&GMIMC_BN_3_PARAMS);
+        let input: Vec<Scalar> = vec![Scalar::from(0), Scalar::from(1), Scalar::from(2)];
+        let perm = gmimc.permutation(&input);
+        assert_eq!(
+            perm[0],
+            from_hex("0x059700e50294cd5c81fd641dc0eb49c993cb8b3bce5e37dc03ac8afdae02c964")
+        );
+        assert_eq!(
+            perm[1],
+            from_hex("0x2bab97a2f53a8ac3d0a7335798de7983297379616a07fffc56196198a198ea0b")
+        );
+        assert_eq!(
+            perm[2],
+            from_hex("0x10cfe7a98f79c9bbb385395762e03dd0290e9621c7d4750362d018459759db8e")
+        );
+    }
+
+    #[test]
+    fn invalid_params() {
+        let new = |t, d, rounds| GmimcParams::<Scalar>::new(t, d, rounds).err();
+        assert_eq!(new(3, 5, 226), None);
+        assert_eq!(new(3, 3, 226), Some(ParamsError::InvalidSboxDegree(3)));
+        assert_eq!(new(3, 1, 226), Some(ParamsError::InvalidSboxDegree(1)));
+        assert_eq!(new(1, 5, 226), Some(ParamsError::InvalidStateSize(1)));
+        assert_eq!(new(0, 5, 226), Some(ParamsError::InvalidStateSize(0)));
+        assert_eq!(new(3, 5, 0), Some(ParamsError::ZeroRounds));
+    }
 }
 
 #[cfg(test)]
 mod gmimc_tests_goldilocks {
     use super::*;
-    use crate::fields::{goldilocks::FpGoldiLocks, utils::random_scalar};
+    use crate::fields::{goldilocks::FpGoldiLocks, utils::from_hex, utils::random_scalar};
     use crate::gmimc::gmimc_instance_goldilocks::{
         GMIMC_GOLDILOCKS_8_PARAMS,
         GMIMC_GOLDILOCKS_12_PARAMS,
@@ -277,6 +326,31 @@ mod gmimc_tests_goldilocks {
             }
         }
     }
+
+    #[test]
+    fn kats() {
+        assert_eq!(
+            GMIMC_GOLDILOCKS_8_PARAMS.round_constants[..2],
+            [from_hex("0x9baaf8fa1d9a9b9e"), from_hex("0xb0e9c6be453379f0")]
+        );
+        let gmimc = Gmimc::new(&GMIMC_GOLDILOCKS_8_PARAMS);
+        let input: Vec<Scalar> = (0..8).map(Scalar::from).collect();
+        let perm = gmimc.permutation(&input);
+        let expected: Vec<Scalar> = [
+            "0x3e6d78df6a31748c",
+            "0xad6306f80a6589e6",
+            "0xa4fa17b8bf309c84",
+            "0x4188cc542202bb06",
+            "0x836545c434fdc07c",
+            "0x6e2eae91dd22b2d5",
+            "0xae82b60cf2a205ef",
+            "0xa5a3a39c7ce827b1",
+        ]
+        .iter()
+        .map(|s| from_hex(s))
+        .collect();
+        assert_eq!(perm, expected);
+    }
 }
 
 #[cfg(test)]
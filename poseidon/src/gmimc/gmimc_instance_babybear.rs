@@ -10,7 +10,7 @@ lazy_static! {
     // Number of rounds:
     // max(2 + 2 * (t + t^2), ceil(2 * log_d(p)) + 2 * t)
     pub static ref GMIMC_BABYBEAR_16_PARAMS: Arc<GmimcParams<Scalar>> =
-        Arc::new(GmimcParams::new(16, 7, 546));
+        Arc::new(GmimcParams::new(16, 7, 546).unwrap());
     pub static ref GMIMC_BABYBEAR_24_PARAMS: Arc<GmimcParams<Scalar>> =
-        Arc::new(GmimcParams::new(24, 7, 1202));
+        Arc::new(GmimcParams::new(24, 7, 1202).unwrap());
 }
@@ -10,21 +10,21 @@ lazy_static! {
     // Number of rounds:
     // max(2 + 2 * (t + t^2), ceil(2 * log_d(p)) + 2 * t)
     pub static ref GMIMC_BN_3_PARAMS: Arc<GmimcParams<Scalar>> =
-        Arc::new(GmimcParams::new(3, 5, 226));
+        Arc::new(GmimcParams::new(3, 5, 226).unwrap());
     pub static ref GMIMC_BN_4_PARAMS: Arc<GmimcParams<Scalar>> =
-        Arc::new(GmimcParams::new(4, 5, 228));
+        Arc::new(GmimcParams::new(4, 5, 228).unwrap());
     pub static ref GMIMC_BN_5_PARAMS: Arc<GmimcParams<Scalar>> =
-        Arc::new(GmimcParams::new(5, 5, 230));
+        Arc::new(GmimcParams::new(5, 5, 230).unwrap());
     pub static ref GMIMC_BN_8_PARAMS: Arc<GmimcParams<Scalar>> =
-        Arc::new(GmimcParams::new(8, 5, 236));
+        Arc::new(GmimcParams::new(8, 5, 236).unwrap());
     pub static ref GMIMC_BN_9_PARAMS: Arc<GmimcParams<Scalar>> =
-        Arc::new(GmimcParams::new(9, 5, 238));
+        Arc::new(GmimcParams::new(9, 5, 238).unwrap());
     pub static ref GMIMC_BN_12_PARAMS: Arc<GmimcParams<Scalar>> =
-        Arc::new(GmimcParams::new(12, 5, 314));
+        Arc::new(GmimcParams::new(12, 5, 314).unwrap());
     pub static ref GMIMC_BN_16_PARAMS: Arc<GmimcParams<Scalar>> =
-        Arc::new(GmimcParams::new(16, 5, 546));
+        Arc::new(GmimcParams::new(16, 5, 546).unwrap());
     pub static ref GMIMC_BN_20_PARAMS: Arc<GmimcParams<Scalar>> =
-        Arc::new(GmimcParams::new(20, 5, 842));
+        Arc::new(GmimcParams::new(20, 5, 842).unwrap());
     pub static ref GMIMC_BN_24_PARAMS: Arc<GmimcParams<Scalar>> =
-        Arc::new(GmimcParams::new(24, 5, 1202));
+        Arc::new(GmimcParams::new(24, 5, 1202).unwrap());
 }
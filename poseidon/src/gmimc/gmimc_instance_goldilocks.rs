@@ -10,11 +10,11 @@ lazy_static! {
     // Number of rounds:
     // max(2 + 2 * (t + t^2), ceil(2 * log_d(p)) + 2 * t)
     pub static ref GMIMC_GOLDILOCKS_8_PARAMS: Arc<GmimcParams<Scalar>> =
-        Arc::new(GmimcParams::new(8, 7, 146));
+        Arc::new(GmimcParams::new(8, 7, 146).unwrap());
     pub static ref GMIMC_GOLDILOCKS_12_PARAMS: Arc<GmimcParams<Scalar>> =
-        Arc::new(GmimcParams::new(12, 7, 314));
+        Arc::new(GmimcParams::new(12, 7, 314).unwrap());
     pub static ref GMIMC_GOLDILOCKS_16_PARAMS: Arc<GmimcParams<Scalar>> =
-        Arc::new(GmimcParams::new(16, 7, 546));
+        Arc::new(GmimcParams::new(16, 7, 546).unwrap());
     pub static ref GMIMC_GOLDILOCKS_20_PARAMS: Arc<GmimcParams<Scalar>> =
-        Arc::new(GmimcParams::new(20, 7, 842));
+        Arc::new(GmimcParams::new(20, 7, 842).unwrap());
 }
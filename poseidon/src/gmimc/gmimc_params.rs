@@ -1,6 +1,11 @@
 use ark_ff::PrimeField;
+use sha3::{
+    digest::{ExtendableOutput, Update, XofReader},
+    Shake128,
+};
 
 use crate::fields::utils;
+use crate::utils::{is_sbox_permutation, ParamsError};
 
 #[derive(Clone, Debug)]
 pub struct GmimcParams<S: PrimeField> {
@@ -11,34 +16,43 @@ pub struct GmimcParams<S: PrimeField> {
 }
 
 impl<S: PrimeField> GmimcParams<S> {
-    // pub const INIT_SHAKE: &'static str = "GMiMC";
+    pub const INIT_SHAKE: &'static str = "GMiMC";
 
-    pub fn new(t: usize, d: usize, rounds: usize) -> Self {
-        assert!(d == 3 || d == 5 || d == 7);
-        // let mut shake = Self::init_shake();
-        // let round_constants = Self::instantiate_rc(rounds, &mut shake);
-        let round_constants = Self::instantiate_rc(rounds);
+    pub fn new(t: usize, d: usize, rounds: usize) -> Result<Self, ParamsError> {
+        if !is_sbox_permutation::<S>(d) {
+            return Err(ParamsError::InvalidSboxDegree(d));
+        }
+        // The Feistel round feeds the first lane into the others
+        if t < 2 {
+            return Err(ParamsError::InvalidStateSize(t));
+        }
+        if rounds == 0 {
+            return Err(ParamsError::ZeroRounds);
+        }
+        let mut shake = Self::init_shake();
+        let round_constants = Self::instantiate_rc(rounds, &mut shake);
 
-        GmimcParams {
+        Ok(GmimcParams {
             t,
             d,
             rounds,
             round_constants,
-        }
+        })
     }
 
-    // fn init_shake() -> XofReaderCoreWrapper<Shake128ReaderCore> {
-    //     let mut shake = Shake128::default();
-    //     shake.update(Self::INIT_SHAKE.as_bytes());
-    //     for i in S::char().as_ref() {
-    //         shake.update(&u64::to_le_bytes(*i));
-    //     }
-    //     shake.finalize_xof()
-    // }
+    // SHAKE128 seeded with INIT_SHAKE and the little-endian limbs of the modulus
+    fn init_shake() -> impl XofReader {
+        let mut shake = Shake128::default();
+        shake.update(Self::INIT_SHAKE.as_bytes());
+        for i in S::characteristic() {
+            shake.update(&u64::to_le_bytes(*i));
+        }
+        shake.finalize_xof()
+    }
 
-    fn instantiate_rc(rounds: usize) -> Vec<S> {
+    fn instantiate_rc(rounds: usize, shake: &mut dyn XofReader) -> Vec<S> {
         (0..rounds)
-            .map(|_| utils::random_scalar())
+            .map(|_| utils::field_element_from_shake(shake))
             .collect()
     }
 
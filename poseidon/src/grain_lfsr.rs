@@ -0,0 +1,92 @@
+use ark_ff::{BigInteger, PrimeField};
+use std::collections::VecDeque;
+
+/// The Grain LFSR of the Poseidon reference scripts (`generate_parameters_grain.sage`),
+/// seeded with the instance description and used to derive round constants and
+/// MDS matrices deterministically.
+#[derive(Clone, Debug)]
+pub struct GrainLfsr {
+    state: VecDeque<bool>,
+}
+
+impl GrainLfsr {
+    /// Seeds the 80-bit state with the field type (2 bits, 1 for prime fields), the
+    /// S-box tag (4 bits), the field size in bits (12 bits), the state size (12 bits),
+    /// R_F (10 bits), R_P (10 bits) and 30 one bits, then discards 160 output bits.
+    pub fn new(
+        field: u16,
+        sbox: u16,
+        field_size: u16,
+        t: u16,
+        rounds_f: u16,
+        rounds_p: u16,
+    ) -> Self {
+        let mut state = VecDeque::with_capacity(80);
+        for (value, bits) in [
+            (field, 2),
+            (sbox, 4),
+            (field_size, 12),
+            (t, 12),
+            (rounds_f, 10),
+            (rounds_p, 10),
+        ] {
+            assert!(value < 1 << bits);
+            state.extend((0..bits).rev().map(|i| (value >> i) & 1 == 1));
+        }
+        state.extend([true; 30]);
+
+        let mut lfsr = GrainLfsr { state };
+        for _ in 0..160 {
+            lfsr.update();
+        }
+        lfsr
+    }
+
+    fn update(&mut self) -> bool {
+        let s = &self.state;
+        let new_bit = s[62] ^ s[51] ^ s[38] ^ s[23] ^ s[13] ^ s[0];
+        self.state.pop_front();
+        self.state.push_back(new_bit);
+        new_bit
+    }
+
+    /// Next output bit. Bits are produced in pairs, the second one is only
+    /// output if the first one is set.
+    pub fn next_bit(&mut self) -> bool {
+        loop {
+            let first = self.update();
+            let second = self.update();
+            if first {
+                return second;
+            }
+        }
+    }
+
+    /// Next `n` output bits, the first one being the most significant.
+    pub fn random_bits(&mut self, n: usize) -> Vec<bool> {
+        (0..n).map(|_| self.next_bit()).collect()
+    }
+
+    /// Samples `MODULUS_BIT_SIZE` bits until they encode an integer below the modulus.
+    pub fn field_element<F: PrimeField>(&mut self) -> F {
+        loop {
+            let bits = self.random_bits(F::MODULUS_BIT_SIZE as usize);
+            if let Some(element) = F::from_bigint(F::BigInt::from_bits_be(&bits)) {
+                return element;
+            }
+        }
+    }
+
+    /// Samples `MODULUS_BIT_SIZE` bits and reduces them modulo p.
+    pub fn field_element_mod_order<F: PrimeField>(&mut self) -> F {
+        let bits = self.random_bits(F::MODULUS_BIT_SIZE as usize);
+        let mut bytes = vec![0u8; bits.len().div_ceil(8)];
+        let offset = bytes.len() * 8 - bits.len();
+        for (i, bit) in bits.iter().enumerate() {
+            if *bit {
+                bytes[(offset + i) / 8] |= 0x80 >> ((offset + i) % 8);
+            }
+        }
+        F::from_be_bytes_mod_order(&bytes)
+    }
+}
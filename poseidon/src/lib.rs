@@ -7,8 +7,12 @@ pub extern crate ark_ff;
 
 pub mod fields;
 pub mod gmimc;
+pub mod grain_lfsr;
 pub mod merkle_tree;
 pub mod neptune;
+pub mod permutation;
 pub mod poseidon;
 pub mod poseidon2;
+pub mod round_numbers;
+pub mod sponge;
 pub mod utils;
@@ -1,4 +1,5 @@
 use crate::merkle_tree::merkle_tree_fp::MerkleTreeHash;
+use crate::permutation::{Permutation, PermutationKind};
 
 use super::neptune_params::NeptuneParams;
 use ark_ff::PrimeField;
@@ -67,9 +68,7 @@ impl<S: PrimeField> Neptune<S> {
                 out.mul_assign(input);
                 out
             }
-            _ => {
-                panic!();
-            }
+            d => input.pow([d as u64]),
         }
     }
 
@@ -198,19 +197,15 @@ impl<S: PrimeField> Neptune<S> {
             return Self::external_matmul_8(input);
         }
 
+        // Other widths keep the generic product, there is no structured multiply for them:
+        // M' on the even lanes and M'' on the odd lanes, skipping the zero half of M_E
         let mut out = vec![S::zero(); t];
-        let t_ = t >> 1;
-        for row in 0..t_ {
-            for col in 0..t_ {
-                // even rows
-                let mut tmp_e = self.params.m_e[2 * row][2 * col];
-                tmp_e.mul_assign(&input[2 * col]);
-                out[2 * row].add_assign(&tmp_e);
-
-                // odd rows
-                let mut tmp_o = self.params.m_e[2 * row + 1][2 * col + 1];
-                tmp_o.mul_assign(&input[2 * col + 1]);
-                out[2 * row + 1].add_assign(&tmp_o);
+        for (lane, block) in self.params.m_blocks.iter().enumerate() {
+            for (row, block_row) in block.iter().enumerate() {
+                out[2 * row + lane] = block_row
+                    .iter()
+                    .zip(input[lane..].iter().step_by(2))
+                    .fold(S::zero(), |acc, (m, x)| acc + *m * x);
             }
         }
         out
@@ -252,6 +247,20 @@ impl<S: PrimeField> Neptune<S> {
     }
 }
 
+impl<S: PrimeField> Permutation<S> for Neptune<S> {
+    fn get_t(&self) -> usize {
+        Neptune::get_t(self)
+    }
+
+    fn permutation(&self, input: &[S]) -> Vec<S> {
+        Neptune::permutation(self, input)
+    }
+
+    fn kind(&self) -> PermutationKind {
+        PermutationKind::Neptune
+    }
+}
+
 impl<S: PrimeField> MerkleTreeHash<S> for Neptune<S> {
     fn compress(&self, input: &[&S]) -> S {
         self.permutation(&[
@@ -263,6 +272,21 @@ impl<S: PrimeField> MerkleTreeHash<S> for Neptune<S> {
     }
 }
 
+// Dense M_E from the M' and M'' blocks, to check external_matmul against
+#[cfg(test)]
+fn build_me<S: PrimeField>(neptune_params: &NeptuneParams<S>) -> Vec<Vec<S>> {
+    let t = neptune_params.t;
+    let mut me = vec![vec![S::zero(); t]; t];
+    for (lane, block) in neptune_params.m_blocks.iter().enumerate() {
+        for (row, block_row) in block.iter().enumerate() {
+            for (col, el) in block_row.iter().enumerate() {
+                me[2 * row + lane][2 * col + lane] = *el;
+            }
+        }
+    }
+    me
+}
+
 #[cfg(test)]
 mod neptune_tests_bls12 {
     use super::*;
@@ -289,6 +313,7 @@ mod neptune_tests_bls12 {
         out
     }
 
+    // M_E with M' on the even and M'' on the odd lanes
     fn build_mi(neptune_params: &Arc<NeptuneParams<Scalar>>) -> Vec<Vec<Scalar>> {
         let t = neptune_params.t;
         let mut mi = vec![vec![Scalar::from(1); t]; t];
@@ -300,12 +325,12 @@ mod neptune_tests_bls12 {
     }
 
     fn matmul_equalities(t: usize) {
-        let neptune_params = Arc::new(NeptuneParams::<Scalar>::new(t, 3, 2, 1));
+        let neptune_params = Arc::new(NeptuneParams::<Scalar>::new(t, 5, 2, 1).unwrap());
         let neptune = Neptune::new(&neptune_params);
         let t = neptune.params.t;
 
         // check external matrix
-        let me = &neptune_params.m_e;
+        let me = build_me(&neptune_params);
         for (row, matrow) in me.iter().enumerate().take(t) {
             for (col, matrowcol) in matrow.iter().enumerate().take(t) {
                 if (row + col) % 2 == 0 {
@@ -320,7 +345,7 @@ mod neptune_tests_bls12 {
         for _ in 0..TESTRUNS {
             let input: Vec<Scalar> = (0..t).map(|_| utils::random_scalar()).collect();
             let external1 = neptune.external_matmul(&input);
-            let external2 = matmul(&input, me);
+            let external2 = matmul(&input, &me);
             assert_eq!(external1, external2);
 
             let internal1 = neptune.internal_matmul(&input);
@@ -408,6 +433,7 @@ mod neptune_tests_bn256 {
         out
     }
 
+    // M_E with M' on the even and M'' on the odd lanes
     fn build_mi(neptune_params: &Arc<NeptuneParams<Scalar>>) -> Vec<Vec<Scalar>> {
         let t = neptune_params.t;
         let mut mi = vec![vec![Scalar::from(1); t]; t];
@@ -419,12 +445,12 @@ mod neptune_tests_bn256 {
     }
 
     fn matmul_equalities(t: usize) {
-        let neptune_params = Arc::new(NeptuneParams::<Scalar>::new(t, 3, 2, 1));
+        let neptune_params = Arc::new(NeptuneParams::<Scalar>::new(t, 5, 2, 1).unwrap());
         let neptune = Neptune::new(&neptune_params);
         let t = neptune.params.t;
 
         // check external matrix
-        let me = &neptune_params.m_e;
+        let me = build_me(&neptune_params);
         for (row, matrow) in me.iter().enumerate().take(t) {
             for (col, matrowcol) in matrow.iter().enumerate().take(t) {
                 if (row + col) % 2 == 0 {
@@ -439,7 +465,7 @@ mod neptune_tests_bn256 {
         for _ in 0..TESTRUNS {
             let input: Vec<Scalar> = (0..t).map(|_| utils::random_scalar()).collect();
             let external1 = neptune.external_matmul(&input);
-            let external2 = matmul(&input, me);
+            let external2 = matmul(&input, &me);
             assert_eq!(external1, external2);
 
             let internal1 = neptune.internal_matmul(&input);
@@ -495,6 +521,28 @@ mod neptune_tests_bn256 {
             assert_ne!(perm1, perm3);
         }
     }
+
+    #[test]
+    fn kats() {
+        // constants are derived from SHAKE128, so they are stable across runs
+        assert_eq!(
+            NEPTUNE_BN_PARAMS.round_constants[0][0],
+            utils::from_hex("0x21b0dea3948d3df03d60c8919fa109a38783fd8a0024cf1eba19b8cd5caad20a")
+        );
+        let neptune = Neptune::new(&NEPTUNE_BN_PARAMS);
+        let input: Vec<Scalar> = (0..4).map(Scalar::from).collect();
+        let perm = neptune.permutation(&input);
+        let expected: Vec<Scalar> = [
+            "0x1c2f0a29cd46c02714e8065e9ea2e62cbf054a61fc60bfb68b220608bb05f7de",
+            "0x0834e189646d4b2f4fe8ad167af82d616874c0bb864067db1129515e6d0ef24e",
+            "0x2c96389769e7762d5964c7caf37a49c19202cb702798b26bc5b88c60a48025db",
+            "0x1482656a9a57a147db56027a1928928ff8817c653053780edebe9f658aebffc4",
+        ]
+        .iter()
+        .map(|s| utils::from_hex(s))
+        .collect();
+        assert_eq!(perm, expected);
+    }
 }
 
 #[cfg(test)]
@@ -525,6 +573,7 @@ mod neptune_tests_goldilocks {
         out
     }
 
+    // M_E with M' on the even and M'' on the odd lanes
     fn build_mi(neptune_params: &Arc<NeptuneParams<Scalar>>) -> Vec<Vec<Scalar>> {
         let t = neptune_params.t;
         let mut mi = vec![vec![Scalar::from(1); t]; t];
@@ -536,12 +585,12 @@ mod neptune_tests_goldilocks {
     }
 
     fn matmul_equalities(t: usize) {
-        let neptune_params = Arc::new(NeptuneParams::<Scalar>::new(t, 3, 2, 1));
+        let neptune_params = Arc::new(NeptuneParams::<Scalar>::new(t, 7, 2, 1).unwrap());
         let neptune = Neptune::new(&neptune_params);
         let t = neptune.params.t;
 
         // check external matrix
-        let me = &neptune_params.m_e;
+        let me = build_me(&neptune_params);
         for (row, matrow) in me.iter().enumerate().take(t) {
             for (col, matrowcol) in matrow.iter().enumerate().take(t) {
                 if (row + col) % 2 == 0 {
@@ -556,7 +605,7 @@ mod neptune_tests_goldilocks {
         for _ in 0..TESTRUNS {
             let input: Vec<Scalar> = (0..t).map(|_| utils::random_scalar()).collect();
             let external1 = neptune.external_matmul(&input);
-            let external2 = matmul(&input, me);
+            let external2 = matmul(&input, &me);
             assert_eq!(external1, external2);
 
             let internal1 = neptune.internal_matmul(&input);
@@ -619,6 +668,49 @@ mod neptune_tests_goldilocks {
             }
         }
     }
+
+    #[test]
+    fn kats() {
+        let neptune = Neptune::new(&NEPTUNE_GOLDILOCKS_8_PARAMS);
+        let input: Vec<Scalar> = (0..8).map(Scalar::from).collect();
+        let perm = neptune.permutation(&input);
+        let expected: Vec<Scalar> = [
+            "0xfcb7ba304be25038",
+            "0x0a5a73194390d17c",
+            "0x8abc1f0b13d091e3",
+            "0xbb1133cb41dbed00",
+            "0x18b8f4187427e45d",
+            "0x16cb835a452ddbce",
+            "0x4540254fd8105059",
+            "0xacf6ede72c4578cf",
+        ]
+        .iter()
+        .map(|s| utils::from_hex(s))
+        .collect();
+        assert_eq!(perm, expected);
+    }
+
+    #[test]
+    fn external_matrix_mds() {
+        // the check is exhaustive, wider Cauchy blocks are MDS by construction
+        let instances: Vec<&Arc<NeptuneParams<Scalar>>> = vec![
+            &NEPTUNE_GOLDILOCKS_8_PARAMS,
+            &NEPTUNE_GOLDILOCKS_12_PARAMS,
+            &NEPTUNE_GOLDILOCKS_16_PARAMS,
+        ];
+        for params in instances {
+            for block in params.m_blocks.iter() {
+                assert!(crate::utils::is_mds(block));
+            }
+        }
+
+        // the top left submatrix [[1, 1], [1, 1]] is singular
+        let mat: Vec<Vec<Scalar>> = [[1u64, 1, 2], [1, 1, 3], [2, 3, 1]]
+            .iter()
+            .map(|row| row.iter().map(|el| Scalar::from(*el)).collect())
+            .collect();
+        assert!(!crate::utils::is_mds(&mat));
+    }
 }
 
 #[cfg(test)]
@@ -628,6 +720,7 @@ mod neptune_tests_babybear {
         fields::{babybear::FpBabyBear, utils},
         neptune::neptune_instances::NEPTUNE_BABYBEAR_16_PARAMS,
         neptune::neptune_instances::NEPTUNE_BABYBEAR_24_PARAMS,
+        utils::ParamsError,
     };
     type Scalar = FpBabyBear;
 
@@ -647,6 +740,7 @@ mod neptune_tests_babybear {
         out
     }
 
+    // M_E with M' on the even and M'' on the odd lanes
     fn build_mi(neptune_params: &Arc<NeptuneParams<Scalar>>) -> Vec<Vec<Scalar>> {
         let t = neptune_params.t;
         let mut mi = vec![vec![Scalar::from(1); t]; t];
@@ -658,12 +752,12 @@ mod neptune_tests_babybear {
     }
 
     fn matmul_equalities(t: usize) {
-        let neptune_params = Arc::new(NeptuneParams::<Scalar>::new(t, 3, 2, 1));
+        let neptune_params = Arc::new(NeptuneParams::<Scalar>::new(t, 7, 2, 1).unwrap());
         let neptune = Neptune::new(&neptune_params);
         let t = neptune.params.t;
 
         // check external matrix
-        let me = &neptune_params.m_e;
+        let me = build_me(&neptune_params);
         for (row, matrow) in me.iter().enumerate().take(t) {
             for (col, matrowcol) in matrow.iter().enumerate().take(t) {
                 if (row + col) % 2 == 0 {
@@ -678,7 +772,7 @@ mod neptune_tests_babybear {
         for _ in 0..TESTRUNS {
             let input: Vec<Scalar> = (0..t).map(|_| utils::random_scalar()).collect();
             let external1 = neptune.external_matmul(&input);
-            let external2 = matmul(&input, me);
+            let external2 = matmul(&input, &me);
             assert_eq!(external1, external2);
 
             let internal1 = neptune.internal_matmul(&input);
@@ -687,6 +781,26 @@ mod neptune_tests_babybear {
         }
     }
 
+    #[test]
+    fn invalid_params() {
+        assert_eq!(
+            NeptuneParams::<Scalar>::new(16, 5, 6, 34).err(),
+            Some(ParamsError::InvalidSboxDegree(5))
+        );
+        assert_eq!(
+            NeptuneParams::<Scalar>::new(15, 7, 6, 34).err(),
+            Some(ParamsError::InvalidStateSize(15))
+        );
+        assert_eq!(
+            NeptuneParams::<Scalar>::new(16, 7, 5, 34).err(),
+            Some(ParamsError::OddFullRounds(5))
+        );
+        assert_eq!(
+            NeptuneParams::<Scalar>::new(16, 7, 6, 0).err(),
+            Some(ParamsError::ZeroPartialRounds)
+        );
+    }
+
     #[test]
     fn matmul_equalities_4() {
         matmul_equalities(4);
@@ -739,4 +853,11 @@ mod neptune_tests_babybear {
             }
         }
     }
+
+    #[test]
+    fn external_matrix_mds() {
+        for block in NEPTUNE_BABYBEAR_16_PARAMS.m_blocks.iter() {
+            assert!(crate::utils::is_mds(block));
+        }
+    }
 }
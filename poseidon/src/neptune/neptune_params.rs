@@ -1,6 +1,11 @@
 use ark_ff::PrimeField;
+use sha3::{
+    digest::{ExtendableOutput, Update, XofReader},
+    Shake128,
+};
 
 use crate::fields::utils;
+use crate::utils::{check_rounds, ParamsError};
 
 #[derive(Clone, Debug)]
 pub struct NeptuneParams<S: PrimeField> {
@@ -12,9 +17,10 @@ pub struct NeptuneParams<S: PrimeField> {
     pub(crate) rounds_f_end: usize,
     pub(crate) rounds: usize,
     pub(crate) round_constants: Vec<Vec<S>>,
-    pub(crate) m_e: Vec<Vec<S>>, // external matrix
-    pub(crate) mu: Vec<S>,       // diagonal of internal matrix
-    pub(crate) abc: [S; 3],      // alpha, beta, gamma
+    // M' and M'' acting on the even and odd lanes
+    pub(crate) m_blocks: [Vec<Vec<S>>; 2],
+    pub(crate) mu: Vec<S>,  // diagonal of internal matrix
+    pub(crate) abc: [S; 3], // alpha, beta, gamma
     #[allow(dead_code)]
     pub(crate) a_: [S; 3], // alpha^2, 3*alpha, 4*alpha
 }
@@ -22,19 +28,21 @@ pub struct NeptuneParams<S: PrimeField> {
 impl<S: PrimeField> NeptuneParams<S> {
     pub const INIT_SHAKE: &'static str = "Neptune";
 
-    pub fn new(t: usize, d: usize, rounds_f: usize, rounds_p: usize) -> Self {
-        assert!(d == 3 || d == 5 || d == 7);
-        assert_eq!(rounds_f % 2, 0);
-        assert_eq!(t % 2, 0);
+    pub fn new(t: usize, d: usize, rounds_f: usize, rounds_p: usize) -> Result<Self, ParamsError> {
+        // The external rounds act on pairs of lanes
+        if t == 0 || t % 2 == 1 {
+            return Err(ParamsError::InvalidStateSize(t));
+        }
+        check_rounds::<S>(d, rounds_f, rounds_p)?;
 
         let r = rounds_f / 2;
         let rounds = rounds_f + rounds_p;
 
-        // let mut shake = Self::init_shake();
-        let round_constants = Self::instantiate_rc(t, rounds);
-        let m_e = Self::instantiate_external_matrix(t);
-        let mu = Self::instantiate_mu(t);
-        let abc = Self::instantiate_abc();
+        let mut shake = Self::init_shake();
+        let round_constants = Self::instantiate_rc(t, rounds, &mut shake);
+        let m_blocks = Self::instantiate_external_blocks(t);
+        let mu = Self::instantiate_mu(t, &mut shake);
+        let abc = Self::instantiate_abc(&mut shake);
 
         // precomputations for more efficient neptune implementation
         let mut a_ = [abc[0]; 3];
@@ -44,7 +52,7 @@ impl<S: PrimeField> NeptuneParams<S> {
         a_[1].add_assign(&abc[0]);
         a_[2].double_in_place();
 
-        NeptuneParams {
+        Ok(NeptuneParams {
             t,
             d,
             rounds_f_beginning: r,
@@ -52,82 +60,85 @@ impl<S: PrimeField> NeptuneParams<S> {
             rounds_f_end: r,
             rounds,
             round_constants,
-            m_e,
+            m_blocks,
             mu,
             abc,
             a_,
-        }
+        })
     }
 
-    // fn init_shake() -> XofReaderCoreWrapper<Shake128ReaderCore> {
-    //     let mut shake = Shake128::default();
-    //     shake.update(Self::INIT_SHAKE.as_bytes());
-    //     for i in S::char().as_ref() {
-    //         shake.update(&u64::to_le_bytes(*i));
-    //     }
-    //     shake.finalize_xof()
-    // }
+    // SHAKE128 seeded with INIT_SHAKE and the little-endian limbs of the modulus
+    fn init_shake() -> impl XofReader {
+        let mut shake = Shake128::default();
+        shake.update(Self::INIT_SHAKE.as_bytes());
+        for i in S::characteristic() {
+            shake.update(&u64::to_le_bytes(*i));
+        }
+        shake.finalize_xof()
+    }
 
-    fn instantiate_rc(t: usize, rounds: usize) -> Vec<Vec<S>> {
+    fn instantiate_rc(t: usize, rounds: usize, shake: &mut dyn XofReader) -> Vec<Vec<S>> {
         (0..rounds)
-            .map(|_| (0..t).map(|_| utils::random_scalar()).collect())
+            .map(|_| {
+                (0..t)
+                    .map(|_| utils::field_element_from_shake(shake))
+                    .collect()
+            })
             .collect()
     }
 
-    fn instantiate_abc() -> [S; 3] {
+    fn instantiate_abc(shake: &mut dyn XofReader) -> [S; 3] {
         let mut abc = [S::one(); 3];
-        abc[2] = utils::random_scalar();
+        abc[2] = utils::field_element_from_shake(shake);
         abc
     }
 
-    fn instantiate_mu(t: usize) -> Vec<S> {
-        // TODO adapt for real instantiation :)
+    fn instantiate_mu(t: usize, shake: &mut dyn XofReader) -> Vec<S> {
         (0..t)
             .map(|_| {
-                let mut tmp = utils::random_scalar_without_0::<S>();
+                let mut tmp = utils::field_element_from_shake_without_0::<S>(shake);
                 tmp.sub_assign(&S::one()); // For faster impl
                 tmp
             })
             .collect()
     }
 
-    fn instantiate_external_matrix(t: usize) -> Vec<Vec<S>> {
+    // M' and M'' are circulant for t = 4 and t = 8 and Cauchy matrices otherwise,
+    // so both are MDS for every even width
+    fn instantiate_external_blocks(t: usize) -> [Vec<Vec<S>>; 2] {
         let t_ = t >> 1;
-        let mut mat = vec![vec![S::zero(); t]; t];
-
-        let m_: Vec<Vec<S>>; // M' matrix
-        let m__: Vec<Vec<S>>; // M'' matrix
 
         if t == 4 {
-            m_ = Self::circ_mat(&[S::from(2 as u64), S::one()]);
-            m__ = Self::circ_mat(&[S::one(), S::from(2 as u64)]);
+            [
+                Self::circ_mat(&[S::from(2u64), S::one()]),
+                Self::circ_mat(&[S::one(), S::from(2u64)]),
+            ]
         } else if t == 8 {
-            m_ = Self::circ_mat(&[S::from(3u64), S::from(2u64), S::from(1u64), S::from(1u64)]);
-            m__ = Self::circ_mat(&[S::from(1u64), S::from(1u64), S::from(2u64), S::from(3u64)]);
+            [
+                Self::circ_mat(&[S::from(3u64), S::from(2u64), S::from(1u64), S::from(1u64)]),
+                Self::circ_mat(&[S::from(1u64), S::from(1u64), S::from(2u64), S::from(3u64)]),
+            ]
         } else {
-            // TODO adapt for real instantiation :)
-            m_ = (0..t_)
-                .map(|_| (0..t_).map(|_| utils::random_scalar()).collect())
-                .collect();
-            m__ = (0..t_)
-                .map(|_| (0..t_).map(|_| utils::random_scalar()).collect())
-                .collect();
-        }
-
-        // M' matrix
-        for row in 0..t_ {
-            for col in 0..t_ {
-                mat[2 * row][2 * col] = m_[row][col];
-            }
+            [
+                Self::cauchy_mat(t_, t_ as u64),
+                Self::cauchy_mat(t_, t as u64),
+            ]
         }
+    }
 
-        // M'' matrix
-        for row in 0..t_ {
-            for col in 0..t_ {
-                mat[2 * row + 1][2 * col + 1] = m__[row][col];
-            }
-        }
-        mat
+    // Cauchy matrix 1 / (x_i + y_j) with x_i = i and y_j = offset + j
+    fn cauchy_mat(t: usize, offset: u64) -> Vec<Vec<S>> {
+        (0..t as u64)
+            .map(|i| {
+                (0..t as u64)
+                    .map(|j| {
+                        S::from(i + offset + j)
+                            .inverse()
+                            .expect("Cauchy matrix entries are non-zero below the characteristic")
+                    })
+                    .collect()
+            })
+            .collect()
     }
 
     fn circ_mat(row: &[S]) -> Vec<Vec<S>> {
@@ -0,0 +1,58 @@
+use ark_ff::PrimeField;
+
+/// Identifies which construction a `Permutation` implements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermutationKind {
+    Poseidon,
+    Poseidon2,
+    Gmimc,
+    Neptune,
+}
+
+/// Common interface of the permutations in this crate, so that hash constructions
+/// can be built on top of any of them.
+pub trait Permutation<F: PrimeField> {
+    /// State width t.
+    fn get_t(&self) -> usize;
+
+    /// Applies the permutation to a state of length t.
+    fn permutation(&self, input: &[F]) -> Vec<F>;
+
+    /// The construction, e.g. to tell Poseidon and Poseidon2 of the same width apart.
+    fn kind(&self) -> PermutationKind;
+}
+
+#[cfg(test)]
+mod permutation_tests {
+    use super::*;
+    use crate::fields::{bn256::FpBN256, utils::random_scalar};
+    use crate::gmimc::{gmimc::Gmimc, gmimc_instance_bn256::GMIMC_BN_3_PARAMS};
+    use crate::neptune::{neptune::Neptune, neptune_instances::NEPTUNE_BN_PARAMS};
+    use crate::poseidon::{poseidon::Poseidon, poseidon_instance_bn256::POSEIDON_BN_PARAMS};
+    use crate::poseidon2::{
+        poseidon2::Poseidon2, poseidon2_instance_bn256::POSEIDON2_BN256_PARAMS,
+    };
+
+    type Scalar = FpBN256;
+
+    fn permute<P: Permutation<Scalar>>(perm: &P, input: &[Scalar]) -> Vec<Scalar> {
+        assert_eq!(perm.get_t(), input.len());
+        perm.permutation(input)
+    }
+
+    #[test]
+    fn dispatch() {
+        let input: Vec<Scalar> = (0..3).map(|_| random_scalar()).collect();
+
+        let poseidon = Poseidon::new(&POSEIDON_BN_PARAMS);
+        assert_eq!(permute(&poseidon, &input), poseidon.permutation(&input));
+        let poseidon2 = Poseidon2::new(&POSEIDON2_BN256_PARAMS);
+        assert_eq!(permute(&poseidon2, &input), poseidon2.permutation(&input));
+        let gmimc = Gmimc::new(&GMIMC_BN_3_PARAMS);
+        assert_eq!(permute(&gmimc, &input), gmimc.permutation(&input));
+
+        let input: Vec<Scalar> = (0..4).map(|_| random_scalar()).collect();
+        let neptune = Neptune::new(&NEPTUNE_BN_PARAMS);
+        assert_eq!(permute(&neptune, &input), neptune.permutation(&input));
+    }
+}
@@ -1,5 +1,6 @@
 use super::poseidon_params::PoseidonParams;
 use crate::merkle_tree::merkle_tree_fp::MerkleTreeHash;
+use crate::permutation::{Permutation, PermutationKind};
 use ark_ff::PrimeField;
 use std::sync::Arc;
 
@@ -102,9 +103,7 @@ impl<S: PrimeField> Poseidon<S> {
                 out.mul_assign(input);
                 out
             }
-            _ => {
-                panic!()
-            }
+            d => input.pow([d as u64]),
         }
     }
 
@@ -157,6 +156,20 @@ impl<S: PrimeField> Poseidon<S> {
     }
 }
 
+impl<F: PrimeField> Permutation<F> for Poseidon<F> {
+    fn get_t(&self) -> usize {
+        Poseidon::get_t(self)
+    }
+
+    fn permutation(&self, input: &[F]) -> Vec<F> {
+        Poseidon::permutation(self, input)
+    }
+
+    fn kind(&self) -> PermutationKind {
+        PermutationKind::Poseidon
+    }
+}
+
 impl<F: PrimeField> MerkleTreeHash<F> for Poseidon<F> {
     fn compress(&self, input: &[&F]) -> F {
         self.permutation(&[input[0].to_owned(), input[1].to_owned(), F::zero()])[0]
@@ -1974,8 +1974,8 @@ lazy_static! {
     ],
     ];
 
-    pub static ref POSEIDON_BABYBEAR_16_PARAMS: Arc<PoseidonParams<Scalar>> = Arc::new(PoseidonParams::new(16, 7, 8, 13, &MDS16, &RC16));
+    pub static ref POSEIDON_BABYBEAR_16_PARAMS: Arc<PoseidonParams<Scalar>> = Arc::new(PoseidonParams::new(16, 7, 8, 13, &MDS16, &RC16).unwrap());
     
-    pub static ref POSEIDON_BABYBEAR_24_PARAMS: Arc<PoseidonParams<Scalar>> = Arc::new(PoseidonParams::new(24, 7, 8, 21, &MDS24, &RC24));
+    pub static ref POSEIDON_BABYBEAR_24_PARAMS: Arc<PoseidonParams<Scalar>> = Arc::new(PoseidonParams::new(24, 7, 8, 21, &MDS24, &RC24).unwrap());
 
     }
@@ -1498,19 +1498,19 @@ lazy_static! {
     ];
 
     pub static ref POSEIDON_BLS_2_PARAMS: Arc<PoseidonParams<Scalar>> = Arc::new(
-        PoseidonParams::new(2, 5, 8, 56, &MDS2, &RC2)
+        PoseidonParams::new(2, 5, 8, 56, &MDS2, &RC2).unwrap()
     );
 
     pub static ref POSEIDON_BLS_3_PARAMS: Arc<PoseidonParams<Scalar>> = Arc::new(
-        PoseidonParams::new(3, 5, 8, 56, &MDS3, &RC3)
+        PoseidonParams::new(3, 5, 8, 56, &MDS3, &RC3).unwrap()
     );
 
     pub static ref POSEIDON_BLS_4_PARAMS: Arc<PoseidonParams<Scalar>> = Arc::new(
-        PoseidonParams::new(4, 5, 8, 56, &MDS4, &RC4)
+        PoseidonParams::new(4, 5, 8, 56, &MDS4, &RC4).unwrap()
     );
 
     pub static ref POSEIDON_BLS_8_PARAMS: Arc<PoseidonParams<Scalar>> = Arc::new(
-        PoseidonParams::new(8, 5, 8, 57, &MDS8, &RC8)
+        PoseidonParams::new(8, 5, 8, 57, &MDS8, &RC8).unwrap()
     );
 
     
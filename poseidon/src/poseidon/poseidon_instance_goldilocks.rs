@@ -2752,16 +2752,16 @@ lazy_static! {
     ],
     ];
 
-    pub static ref POSEIDON_GOLDILOCKS_8_PARAMS: Arc<PoseidonParams<Scalar>> = Arc::new(PoseidonParams::new(8, 7, 8, 22, &MDS8, &RC8)
+    pub static ref POSEIDON_GOLDILOCKS_8_PARAMS: Arc<PoseidonParams<Scalar>> = Arc::new(PoseidonParams::new(8, 7, 8, 22, &MDS8, &RC8).unwrap()
     );
     
-    pub static ref POSEIDON_GOLDILOCKS_12_PARAMS: Arc<PoseidonParams<Scalar>> = Arc::new(PoseidonParams::new(12, 7, 8, 22, &MDS12, &RC12)
+    pub static ref POSEIDON_GOLDILOCKS_12_PARAMS: Arc<PoseidonParams<Scalar>> = Arc::new(PoseidonParams::new(12, 7, 8, 22, &MDS12, &RC12).unwrap()
     );
 
-    pub static ref POSEIDON_GOLDILOCKS_16_PARAMS: Arc<PoseidonParams<Scalar>> = Arc::new(PoseidonParams::new(16, 7, 8, 22, &MDS16, &RC16)
+    pub static ref POSEIDON_GOLDILOCKS_16_PARAMS: Arc<PoseidonParams<Scalar>> = Arc::new(PoseidonParams::new(16, 7, 8, 22, &MDS16, &RC16).unwrap()
     );
 
-    pub static ref POSEIDON_GOLDILOCKS_20_PARAMS: Arc<PoseidonParams<Scalar>> = Arc::new(PoseidonParams::new(20, 7, 8, 22, &MDS20, &RC20)
+    pub static ref POSEIDON_GOLDILOCKS_20_PARAMS: Arc<PoseidonParams<Scalar>> = Arc::new(PoseidonParams::new(20, 7, 8, 22, &MDS20, &RC20).unwrap()
     );
 
 }
\ No newline at end of file
@@ -1294,13 +1294,13 @@ lazy_static! {
 
     pub static ref POSEIDON_PALLAS_3_PARAMS: Arc<PoseidonParams<Scalar>> = Arc::new(PoseidonParams::new(
         3, 5, 8, 56, &MDS3, &RC3
-    ));
+    ).unwrap());
 
     pub static ref POSEIDON_PALLAS_4_PARAMS: Arc<PoseidonParams<Scalar>> = Arc::new(PoseidonParams::new(
         4, 5, 8, 56, &MDS4, &RC4
-    ));
+    ).unwrap());
 
     pub static ref POSEIDON_PALLAS_8_PARAMS: Arc<PoseidonParams<Scalar>> = Arc::new(PoseidonParams::new(
         8, 5, 8, 57, &MDS8, &RC8
-    ));
+    ).unwrap());
 }
@@ -285,5 +285,5 @@ lazy_static! {
 
     pub static ref POSEIDON_VESTA_PARAMS: Arc<PoseidonParams<Scalar>> = Arc::new(PoseidonParams::new(
         3, 5, 8, 56, &MDS3, &RC3
-    ));
+    ).unwrap());
 }
@@ -1,6 +1,10 @@
 use ark_ff::PrimeField;
 
-use crate::utils;
+use crate::grain_lfsr::GrainLfsr;
+use crate::utils::{self, ParamsError};
+
+// S-box tag of the Grain seed the shipped instances were generated with
+const GRAIN_SBOX: u16 = 1;
 
 #[derive(Clone, Debug)]
 pub struct PoseidonParams<S: PrimeField> {
@@ -28,17 +32,20 @@ impl<S: PrimeField> PoseidonParams<S> {
         rounds_p: usize,
         mds: &[Vec<S>],
         round_constants: &[Vec<S>],
-    ) -> Self {
-        assert!(d == 3 || d == 5 || d == 7);
-        assert_eq!(mds.len(), t);
-        assert_eq!(rounds_f % 2, 0);
+    ) -> Result<Self, ParamsError> {
+        if t == 0 {
+            return Err(ParamsError::InvalidStateSize(t));
+        }
+        utils::check_rounds::<S>(d, rounds_f, rounds_p)?;
+        utils::check_matrix("MDS matrix", mds, t, t)?;
+        utils::check_matrix("round constants", round_constants, rounds_f + rounds_p, t)?;
         let r = rounds_f / 2;
         let rounds = rounds_f + rounds_p;
 
         let (m_i_, v_, w_hat_) = Self::equivalent_matrices(mds, t, rounds_p);
         let opt_round_constants_ = Self::equivalent_round_constants(round_constants, mds, r, rounds_p);
 
-        PoseidonParams {
+        Ok(PoseidonParams {
             t,
             d,
             rounds_f_beginning: r,
@@ -51,7 +58,75 @@ impl<S: PrimeField> PoseidonParams<S> {
             w_hat: w_hat_,
             v: v_,
             m_i: m_i_,
+        })
+    }
+
+    /// Derives the round constants and the Cauchy MDS matrix from the Grain LFSR, as the
+    /// reference scripts do, and instantiates the parameters with them. The constants are
+    /// sampled by rejection; the MDS matrix is `1 / (x_i + y_j)` for `2t` distinct
+    /// elements reduced modulo p, resampled if any `x_i + y_j` is zero.
+    /// The subspace trail checks of the reference scripts are not performed.
+    pub fn generate(
+        t: usize,
+        d: usize,
+        rounds_f: usize,
+        rounds_p: usize,
+    ) -> Result<Self, ParamsError> {
+        let (mds, round_constants) = Self::generate_constants(t, rounds_f, rounds_p)?;
+        Self::new(t, d, rounds_f, rounds_p, &mds, &round_constants)
+    }
+
+    /// Returns the Grain LFSR MDS matrix and round constants, one row per round.
+    /// The seed holds t in 12 bits and R_F, R_P in 10 bits each, so t < 4096 and
+    /// R_F, R_P < 1024.
+    #[allow(clippy::type_complexity)]
+    pub fn generate_constants(
+        t: usize,
+        rounds_f: usize,
+        rounds_p: usize,
+    ) -> Result<(Vec<Vec<S>>, Vec<Vec<S>>), ParamsError> {
+        for (what, value, bits) in [("t", t, 12), ("R_F", rounds_f, 10), ("R_P", rounds_p, 10)] {
+            if value >= 1 << bits {
+                return Err(ParamsError::GrainOverflow { what, value, bits });
+            }
         }
+
+        let mut grain = GrainLfsr::new(
+            1,
+            GRAIN_SBOX,
+            S::MODULUS_BIT_SIZE as u16,
+            t as u16,
+            rounds_f as u16,
+            rounds_p as u16,
+        );
+
+        let round_constants = (0..rounds_f + rounds_p)
+            .map(|_| (0..t).map(|_| grain.field_element()).collect())
+            .collect();
+
+        let mds = loop {
+            let mut elements: Vec<S> = Vec::with_capacity(2 * t);
+            while elements.len() < 2 * t {
+                elements = (0..2 * t).map(|_| grain.field_element_mod_order()).collect();
+                let mut sorted = elements.clone();
+                sorted.sort();
+                sorted.dedup();
+                if sorted.len() != 2 * t {
+                    elements.clear();
+                }
+            }
+
+            let (xs, ys) = elements.split_at(t);
+            let mds: Option<Vec<Vec<S>>> = xs
+                .iter()
+                .map(|x| ys.iter().map(|y| (*x + y).inverse()).collect())
+                .collect();
+            if let Some(mds) = mds {
+                break mds;
+            }
+        };
+
+        Ok((mds, round_constants))
     }
 
     #[allow(clippy::type_complexity)]
@@ -151,5 +226,113 @@ impl<S: PrimeField> PoseidonParams<S> {
         }
         out
     }
+}
+
+#[cfg(test)]
+mod poseidon_params_tests {
+    use super::*;
+    use crate::fields::{bls12::FpBLS12, bn256::FpBN256, goldilocks::FpGoldiLocks};
+    use crate::poseidon::{
+        poseidon_instance_bls12, poseidon_instance_bn256, poseidon_instance_goldilocks,
+    };
+
+    fn check<S: PrimeField>(params: &PoseidonParams<S>) {
+        let rounds_f = 2 * params.rounds_f_beginning;
+        let (mds, round_constants) =
+            PoseidonParams::<S>::generate_constants(params.t, rounds_f, params.rounds_p).unwrap();
+        assert_eq!(mds, params.mds);
+        assert_eq!(round_constants, params.round_constants);
+    }
 
+    #[test]
+    fn grain_bn256() {
+        check::<FpBN256>(&poseidon_instance_bn256::POSEIDON_BN_PARAMS);
+    }
+
+    #[test]
+    fn grain_bls12() {
+        check::<FpBLS12>(&poseidon_instance_bls12::POSEIDON_BLS_2_PARAMS);
+        check::<FpBLS12>(&poseidon_instance_bls12::POSEIDON_BLS_3_PARAMS);
+        check::<FpBLS12>(&poseidon_instance_bls12::POSEIDON_BLS_4_PARAMS);
+        check::<FpBLS12>(&poseidon_instance_bls12::POSEIDON_BLS_8_PARAMS);
+    }
+
+    #[test]
+    fn grain_goldilocks() {
+        check::<FpGoldiLocks>(&poseidon_instance_goldilocks::POSEIDON_GOLDILOCKS_8_PARAMS);
+        check::<FpGoldiLocks>(&poseidon_instance_goldilocks::POSEIDON_GOLDILOCKS_12_PARAMS);
+        check::<FpGoldiLocks>(&poseidon_instance_goldilocks::POSEIDON_GOLDILOCKS_16_PARAMS);
+        check::<FpGoldiLocks>(&poseidon_instance_goldilocks::POSEIDON_GOLDILOCKS_20_PARAMS);
+
+        // a width without a table
+        let params = PoseidonParams::<FpGoldiLocks>::generate(24, 7, 8, 22).unwrap();
+        assert_eq!(params.round_constants.len(), 30);
+        assert_eq!(params.mds.len(), 24);
+    }
+
+    #[test]
+    fn invalid_params() {
+        let params = &poseidon_instance_bn256::POSEIDON_BN_PARAMS;
+        let new = |t, d, rounds_f, rounds_p| {
+            PoseidonParams::<FpBN256>::new(
+                t,
+                d,
+                rounds_f,
+                rounds_p,
+                &params.mds,
+                &params.round_constants,
+            )
+            .err()
+        };
+        assert_eq!(new(3, 5, 8, 56), None);
+        assert_eq!(new(0, 5, 8, 56), Some(ParamsError::InvalidStateSize(0)));
+        assert_eq!(new(3, 3, 8, 56), Some(ParamsError::InvalidSboxDegree(3)));
+        assert_eq!(new(3, 5, 7, 57), Some(ParamsError::OddFullRounds(7)));
+        assert_eq!(new(3, 5, 64, 0), Some(ParamsError::ZeroPartialRounds));
+        assert_eq!(
+            new(4, 5, 8, 56),
+            Some(ParamsError::DimensionMismatch {
+                what: "MDS matrix",
+                expected: 4,
+                found: 3,
+            })
+        );
+        assert_eq!(
+            new(3, 5, 8, 57),
+            Some(ParamsError::DimensionMismatch {
+                what: "round constants",
+                expected: 65,
+                found: 64,
+            })
+        );
+
+        // the Grain LFSR seed bounds t, R_F and R_P
+        let generate = |t, rounds_f, rounds_p| {
+            PoseidonParams::<FpGoldiLocks>::generate(t, 7, rounds_f, rounds_p).err()
+        };
+        assert_eq!(
+            generate(4096, 8, 22),
+            Some(ParamsError::GrainOverflow {
+                what: "t",
+                value: 4096,
+                bits: 12,
+            })
+        );
+        assert_eq!(
+            generate(8, 1024, 22),
+            Some(ParamsError::GrainOverflow {
+                what: "R_F",
+                value: 1024,
+                bits: 10,
+            })
+        );
+        assert_eq!(
+            generate(8, 8, 1024),
+            Some(ParamsError::GrainOverflow {
+                what: "R_P",
+                value: 1024,
+                bits: 10,
+            })
+        );
+    }
 }
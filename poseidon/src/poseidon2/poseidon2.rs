@@ -1,5 +1,6 @@
 use super::poseidon2_params::Poseidon2Params;
 use crate::merkle_tree::merkle_tree_fp::MerkleTreeHash;
+use crate::permutation::{Permutation, PermutationKind};
 use ark_ff::PrimeField;
 use std::sync::Arc;
 
@@ -76,9 +77,15 @@ impl<F: PrimeField> Poseidon2<F> {
                 out.mul_assign(input);
                 out
             }
-            _ => {
-                panic!()
+            11 => {
+                let mut out = input2;
+                out.square_in_place();
+                out.square_in_place();
+                out.mul_assign(&input2);
+                out.mul_assign(input);
+                out
             }
+            d => input.pow([d as u64]),
         }
     }
 
@@ -156,9 +163,7 @@ impl<F: PrimeField> Poseidon2<F> {
                     input[i].add_assign(&stored[i % 4]);
                 }
             }
-            _ => {
-                panic!()
-            }
+            _ => unreachable!("state size is checked by Poseidon2Params::new"),
         }
     }
 
@@ -201,9 +206,7 @@ impl<F: PrimeField> Poseidon2<F> {
                     input[i].add_assign(&sum);
                 }
             }
-            _ => {
-                panic!()
-            }
+            _ => unreachable!("state size is checked by Poseidon2Params::new"),
         }
     }
 
@@ -220,6 +223,20 @@ impl<F: PrimeField> Poseidon2<F> {
     }
 }
 
+impl<F: PrimeField> Permutation<F> for Poseidon2<F> {
+    fn get_t(&self) -> usize {
+        Poseidon2::get_t(self)
+    }
+
+    fn permutation(&self, input: &[F]) -> Vec<F> {
+        Poseidon2::permutation(self, input)
+    }
+
+    fn kind(&self) -> PermutationKind {
+        PermutationKind::Poseidon2
+    }
+}
+
 impl<F: PrimeField> MerkleTreeHash<F> for Poseidon2<F> {
     fn compress(&self, input: &[&F]) -> F {
         self.permutation(&[input[0].to_owned(), input[1].to_owned(), F::zero()])[0]
@@ -302,10 +319,13 @@ mod poseidon2_tests_goldilocks {
 #[cfg(test)]
 mod poseidon2_tests_babybear {
     use super::*;
+    use ark_ff::Field;
     use crate::fields::{babybear::FpBabyBear, utils::from_hex, utils::random_scalar};
     use crate::poseidon2::poseidon2_instance_babybear::{
-        POSEIDON2_BABYBEAR_16_PARAMS, POSEIDON2_BABYBEAR_24_PARAMS,
+        MAT_DIAG16_M_1, MAT_INTERNAL16, POSEIDON2_BABYBEAR_16_PARAMS, POSEIDON2_BABYBEAR_24_PARAMS,
+        RC16,
     };
+    use crate::utils::ParamsError;
     use std::convert::TryFrom;
 
     type Scalar = FpBabyBear;
@@ -373,6 +393,121 @@ mod poseidon2_tests_babybear {
         assert_eq!(perm[22], from_hex("0x3c985ba2"));
         assert_eq!(perm[23], from_hex("0x57a99864"));
     }
+
+    // Textbook Poseidon2 with dense matrices and x^d computed by pow, used as a reference
+    // for the optimized permutation. M_E = circ(2 M_4, M_4, ...) and M_I = 1 + diag(mu - 1).
+    fn reference_permutation(params: &Poseidon2Params<Scalar>, input: &[Scalar]) -> Vec<Scalar> {
+        let t = params.t;
+        let m4 = [[5u64, 7, 1, 3], [4, 6, 1, 1], [1, 3, 5, 7], [1, 1, 4, 6]];
+        let m_e: Vec<Vec<Scalar>> = (0..t)
+            .map(|row| {
+                (0..t)
+                    .map(|col| {
+                        let factor = if row / 4 == col / 4 { 2 } else { 1 };
+                        Scalar::from(factor * m4[row % 4][col % 4])
+                    })
+                    .collect()
+            })
+            .collect();
+        let m_i: Vec<Vec<Scalar>> = (0..t)
+            .map(|row| {
+                (0..t)
+                    .map(|col| match row == col {
+                        true => params.mat_internal_diag_m_1[row] + Scalar::from(1u64),
+                        false => Scalar::from(1u64),
+                    })
+                    .collect()
+            })
+            .collect();
+        let sbox = |x: Scalar| x.pow([params.d as u64]);
+
+        let p_start = params.rounds_f_beginning;
+        let p_end = p_start + params.rounds_p;
+        let mut state = Poseidon2Params::mat_vec_mul(&m_e, input);
+        for (r, rc) in params.round_constants.iter().enumerate() {
+            if (p_start..p_end).contains(&r) {
+                state[0] = sbox(state[0] + rc[0]);
+                state = Poseidon2Params::mat_vec_mul(&m_i, &state);
+            } else {
+                state = state.iter().zip(rc).map(|(x, c)| sbox(*x + c)).collect();
+                state = Poseidon2Params::mat_vec_mul(&m_e, &state);
+            }
+        }
+        state
+    }
+
+    #[test]
+    fn reference_kat() {
+        // the reference reproduces the t = 24 known-answer vector of kats()
+        let input: Vec<Scalar> = (0..24).map(|i| Scalar::from(i as u64)).collect();
+        let perm = reference_permutation(&POSEIDON2_BABYBEAR_24_PARAMS, &input);
+        assert_eq!(perm[0], from_hex("0x2ed3e23d"));
+        assert_eq!(perm[23], from_hex("0x57a99864"));
+    }
+
+    #[test]
+    fn sbox_degrees() {
+        // p - 1 = 2^27 * 3 * 5, so x^3 and x^5 are not permutations of BabyBear
+        assert!(!crate::utils::is_sbox_permutation::<Scalar>(3));
+        assert!(!crate::utils::is_sbox_permutation::<Scalar>(5));
+        assert!(crate::utils::is_sbox_permutation::<Scalar>(7));
+        assert!(crate::utils::is_sbox_permutation::<Scalar>(11));
+        assert!(crate::utils::is_sbox_permutation::<Scalar>(13));
+
+        for d in [7usize, 11, 13] {
+            let params = Arc::new(
+                Poseidon2Params::new(16, d, 8, 13, &MAT_DIAG16_M_1, &MAT_INTERNAL16, &RC16)
+                    .unwrap(),
+            );
+            let poseidon2 = Poseidon2::new(&params);
+            let input: Scalar = random_scalar();
+            assert_eq!(poseidon2.sbox_p(&input), input.pow([d as u64]));
+
+            let input: Vec<Scalar> = (0..16).map(|_| random_scalar()).collect();
+            assert_eq!(
+                poseidon2.permutation(&input),
+                reference_permutation(&params, &input)
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_params() {
+        let new = |t, d, rounds_f, rounds_p| {
+            Poseidon2Params::new(
+                t,
+                d,
+                rounds_f,
+                rounds_p,
+                &MAT_DIAG16_M_1,
+                &MAT_INTERNAL16,
+                &RC16,
+            )
+            .err()
+        };
+        assert_eq!(new(16, 7, 8, 13), None);
+        assert_eq!(new(16, 5, 8, 13), Some(ParamsError::InvalidSboxDegree(5)));
+        assert_eq!(new(16, 1, 8, 13), Some(ParamsError::InvalidSboxDegree(1)));
+        assert_eq!(new(15, 7, 8, 13), Some(ParamsError::InvalidStateSize(15)));
+        assert_eq!(new(16, 7, 7, 14), Some(ParamsError::OddFullRounds(7)));
+        assert_eq!(new(16, 7, 20, 0), Some(ParamsError::ZeroPartialRounds));
+        assert_eq!(
+            new(16, 7, 8, 12),
+            Some(ParamsError::DimensionMismatch {
+                what: "round constants",
+                expected: 20,
+                found: 21,
+            })
+        );
+        assert_eq!(
+            new(12, 7, 8, 13),
+            Some(ParamsError::DimensionMismatch {
+                what: "internal diagonal",
+                expected: 12,
+                found: 16,
+            })
+        );
+    }
 }
 
 #[allow(unused_imports)]
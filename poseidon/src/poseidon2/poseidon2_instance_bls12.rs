@@ -1526,11 +1526,11 @@ lazy_static! {
     ],
     ];
 
-    pub static ref POSEIDON2_BLS_2_PARAMS: Arc<Poseidon2Params<Scalar>> = Arc::new(Poseidon2Params::new(2, 5, 8, 56, &MAT_DIAG2_M_1, &MAT_INTERNAL2, &RC2));
+    pub static ref POSEIDON2_BLS_2_PARAMS: Arc<Poseidon2Params<Scalar>> = Arc::new(Poseidon2Params::new(2, 5, 8, 56, &MAT_DIAG2_M_1, &MAT_INTERNAL2, &RC2).unwrap());
     
-    pub static ref POSEIDON2_BLS_3_PARAMS: Arc<Poseidon2Params<Scalar>> = Arc::new(Poseidon2Params::new(3, 5, 8, 56, &MAT_DIAG3_M_1, &MAT_INTERNAL3, &RC3));
+    pub static ref POSEIDON2_BLS_3_PARAMS: Arc<Poseidon2Params<Scalar>> = Arc::new(Poseidon2Params::new(3, 5, 8, 56, &MAT_DIAG3_M_1, &MAT_INTERNAL3, &RC3).unwrap());
 
-    pub static ref POSEIDON2_BLS_4_PARAMS: Arc<Poseidon2Params<Scalar>> = Arc::new(Poseidon2Params::new(4, 5, 8, 56, &MAT_DIAG4_M_1, &MAT_INTERNAL4, &RC4));
+    pub static ref POSEIDON2_BLS_4_PARAMS: Arc<Poseidon2Params<Scalar>> = Arc::new(Poseidon2Params::new(4, 5, 8, 56, &MAT_DIAG4_M_1, &MAT_INTERNAL4, &RC4).unwrap());
 
-    pub static ref POSEIDON2_BLS_8_PARAMS: Arc<Poseidon2Params<Scalar>> = Arc::new(Poseidon2Params::new(8, 5, 8, 57, &MAT_DIAG8_M_1, &MAT_INTERNAL8, &RC8));
+    pub static ref POSEIDON2_BLS_8_PARAMS: Arc<Poseidon2Params<Scalar>> = Arc::new(Poseidon2Params::new(8, 5, 8, 57, &MAT_DIAG8_M_1, &MAT_INTERNAL8, &RC8).unwrap());
 }
\ No newline at end of file
@@ -2985,15 +2985,15 @@ lazy_static! {
         ],
     ];
     pub static ref POSEIDON2_GOLDILOCKS_8_PARAMS: Arc<Poseidon2Params<Scalar>> = Arc::new(
-        Poseidon2Params::new(8, 7, 8, 22, &MAT_DIAG8_M_1, &MAT_INTERNAL8, &RC8)
+        Poseidon2Params::new(8, 7, 8, 22, &MAT_DIAG8_M_1, &MAT_INTERNAL8, &RC8).unwrap()
     );
     pub static ref POSEIDON2_GOLDILOCKS_12_PARAMS: Arc<Poseidon2Params<Scalar>> = Arc::new(
-        Poseidon2Params::new(12, 7, 8, 22, &MAT_DIAG12_M_1, &MAT_INTERNAL12, &RC12)
+        Poseidon2Params::new(12, 7, 8, 22, &MAT_DIAG12_M_1, &MAT_INTERNAL12, &RC12).unwrap()
     );
     pub static ref POSEIDON2_GOLDILOCKS_16_PARAMS: Arc<Poseidon2Params<Scalar>> = Arc::new(
-        Poseidon2Params::new(16, 7, 8, 22, &MAT_DIAG16_M_1, &MAT_INTERNAL16, &RC16)
+        Poseidon2Params::new(16, 7, 8, 22, &MAT_DIAG16_M_1, &MAT_INTERNAL16, &RC16).unwrap()
     );
     pub static ref POSEIDON2_GOLDILOCKS_20_PARAMS: Arc<Poseidon2Params<Scalar>> = Arc::new(
-        Poseidon2Params::new(20, 7, 8, 22, &MAT_DIAG20_M_1, &MAT_INTERNAL20, &RC20)
+        Poseidon2Params::new(20, 7, 8, 22, &MAT_DIAG20_M_1, &MAT_INTERNAL20, &RC20).unwrap()
     );
 }
@@ -1,6 +1,6 @@
 use ark_ff::PrimeField;
 
-use crate::utils;
+use crate::utils::{self, ParamsError};
 
 #[derive(Clone, Debug)]
 pub struct Poseidon2Params<F: PrimeField> {
@@ -29,13 +29,19 @@ impl<F: PrimeField> Poseidon2Params<F> {
         mat_internal_diag_m_1: &[F],
         mat_internal: &[Vec<F>],
         round_constants: &[Vec<F>],
-    ) -> Self {
-        assert!(d == 3 || d == 5 || d == 7 || d == 11);
-        assert_eq!(rounds_f % 2, 0);
+    ) -> Result<Self, ParamsError> {
+        // The linear layers are only implemented for these widths
+        if !matches!(t, 2 | 3 | 4 | 8 | 12 | 16 | 20 | 24) {
+            return Err(ParamsError::InvalidStateSize(t));
+        }
+        utils::check_rounds::<F>(d, rounds_f, rounds_p)?;
+        utils::check_dimension("internal diagonal", t, mat_internal_diag_m_1.len())?;
+        utils::check_matrix("internal matrix", mat_internal, t, t)?;
+        utils::check_matrix("round constants", round_constants, rounds_f + rounds_p, t)?;
         let r = rounds_f / 2;
         let rounds = rounds_f + rounds_p;
 
-        Poseidon2Params {
+        Ok(Poseidon2Params {
             t,
             d,
             rounds_f_beginning: r,
@@ -45,7 +51,7 @@ impl<F: PrimeField> Poseidon2Params<F> {
             mat_internal_diag_m_1: mat_internal_diag_m_1.to_owned(),
             _mat_internal: mat_internal.to_owned(),
             round_constants: round_constants.to_owned(),
-        }
+        })
     }
 
     // Unused
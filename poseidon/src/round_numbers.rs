@@ -0,0 +1,333 @@
+use ark_ff::PrimeField;
+
+use crate::utils::{is_sbox_permutation, ParamsError};
+
+// log2(p), precise enough for the bounds below
+fn log2_modulus<F: PrimeField>() -> f64 {
+    F::MODULUS
+        .as_ref()
+        .iter()
+        .rev()
+        .fold(0f64, |acc, limb| acc * 2f64.powi(64) + *limb as f64)
+        .log2()
+}
+
+// log2 of the binomial coefficient (n choose k)
+fn log2_binomial(n: usize, k: usize) -> f64 {
+    (1..=k)
+        .map(|i| ((n - k + i) as f64).log2() - (i as f64).log2())
+        .sum()
+}
+
+fn check_sbox<F: PrimeField>(t: usize, d: usize) -> Result<(), ParamsError> {
+    if !is_sbox_permutation::<F>(d) {
+        return Err(ParamsError::InvalidSboxDegree(d));
+    }
+    if t < 2 {
+        return Err(ParamsError::InvalidStateSize(t));
+    }
+    Ok(())
+}
+
+// Statistical, interpolation and Groebner basis bounds of the Poseidon paper, plus the
+// Groebner basis attack of https://eprint.iacr.org/2023/537 (as in calc_round_numbers.py)
+fn poseidon_secure(
+    log2_p: f64,
+    t: usize,
+    d: usize,
+    security_level: usize,
+    rounds_f: usize,
+    rounds_p: usize,
+) -> bool {
+    let n = log2_p.ceil();
+    let m = security_level as f64;
+    let (t_, d_, rp) = (t as f64, d as f64, rounds_p as f64);
+    let log_d_2 = 2f64.ln() / d_.ln();
+
+    let statistical = if m <= (log2_p - (d_ - 1.0) / 2.0).floor() * (t_ + 1.0) {
+        6.0
+    } else {
+        10.0
+    };
+    let interpolation = 1.0 + (log_d_2 * m.min(n)).ceil() + (t_.ln() / d_.ln()).ceil() - rp;
+    let groebner_1 = log_d_2 * m.min(log2_p) - rp;
+    let groebner_2 = t_ - 1.0 + log_d_2 * (m / (t_ + 1.0)).min(log2_p / 2.0) - rp;
+    let groebner_3 = (t_ - 2.0 + m / (2.0 * d_.log2()) - rp) / (t_ - 1.0);
+    let rounds_f_min = [
+        statistical,
+        interpolation,
+        groebner_1,
+        groebner_2,
+        groebner_3,
+    ]
+    .iter()
+    .fold(f64::NEG_INFINITY, |acc, bound| acc.max(bound.ceil()));
+    if (rounds_f as f64) < rounds_f_min {
+        return false;
+    }
+
+    let r = t / 3;
+    let over = (rounds_f - 1) * t + rounds_p + r + r * rounds_f / 2 + rounds_p + d;
+    let under = r * rounds_f / 2 + rounds_p + d;
+    (2.0 * log2_binomial(over, under)).ceil() >= m
+}
+
+/// Minimal (R_F, R_P) for Poseidon over `F` with state size `t`, S-box `x^d` and
+/// `security_level` bits of security. Searches the cheapest instance in number of
+/// S-boxes, then adds the security margin of the reference script: two full rounds
+/// and 7.5% more partial rounds. Fails with `UnreachableSecurity` if no instance with
+/// R_F < 100 and R_P < 500 is secure.
+pub fn poseidon_rounds<F: PrimeField>(
+    t: usize,
+    d: usize,
+    security_level: usize,
+) -> Result<(usize, usize), ParamsError> {
+    check_sbox::<F>(t, d)?;
+    let log2_p = log2_modulus::<F>();
+
+    let mut best: Option<(usize, usize, usize)> = None;
+    for rounds_p in 1..500 {
+        // the bounds only get easier to meet with more full rounds
+        let rounds_f = match (4..100)
+            .step_by(2)
+            .find(|&rf| poseidon_secure(log2_p, t, d, security_level, rf, rounds_p))
+        {
+            Some(rounds_f) => rounds_f + 2,
+            None => continue,
+        };
+        let rounds_p = (rounds_p as f64 * 1.075).ceil() as usize;
+        let cost = rounds_f * t + rounds_p;
+        if best.is_none_or(|(c, rf, _)| cost < c || (cost == c && rounds_f < rf)) {
+            best = Some((cost, rounds_f, rounds_p));
+        }
+    }
+
+    match best {
+        Some((_, rounds_f, rounds_p)) => Ok((rounds_f, rounds_p)),
+        None => Err(ParamsError::UnreachableSecurity(security_level)),
+    }
+}
+
+/// Minimal (R_F, R_P) for Poseidon2. Its security argument is the one of Poseidon,
+/// so the round numbers are the same.
+pub fn poseidon2_rounds<F: PrimeField>(
+    t: usize,
+    d: usize,
+    security_level: usize,
+) -> Result<(usize, usize), ParamsError> {
+    poseidon_rounds::<F>(t, d, security_level)
+}
+
+/// Minimal (R_F, R_P) for Neptune: six external rounds against statistical attacks and
+/// ceil(1.125 * ceil(log_d(2) * (min(kappa, log_2(p)) - 6) + 3 + t + log_d(t)))
+/// internal rounds against algebraic ones.
+pub fn neptune_rounds<F: PrimeField>(
+    t: usize,
+    d: usize,
+    security_level: usize,
+) -> Result<(usize, usize), ParamsError> {
+    check_sbox::<F>(t, d)?;
+    if t % 2 == 1 {
+        return Err(ParamsError::InvalidStateSize(t));
+    }
+
+    let (t_, d_) = (t as f64, d as f64);
+    let algebraic = 2f64.ln() / d_.ln() * ((security_level as f64).min(log2_modulus::<F>()) - 6.0)
+        + 3.0
+        + t_
+        + t_.ln() / d_.ln();
+    let rounds_p = (1.125 * algebraic.ceil()).ceil() as usize;
+    Ok((6, rounds_p))
+}
+
+/// Minimal number of rounds for GMiMC_erf:
+/// max(2 + 2 * (t + t^2), ceil(2 * log_d(p)) + 2 * t). The algebraic bound is stated for
+/// kappa <= log_2(p); over smaller fields the degree has to reach 2^kappa instead.
+pub fn gmimc_rounds<F: PrimeField>(
+    t: usize,
+    d: usize,
+    security_level: usize,
+) -> Result<usize, ParamsError> {
+    check_sbox::<F>(t, d)?;
+
+    let statistical = 2 + 2 * (t + t * t);
+    let log2_p = log2_modulus::<F>().max(security_level as f64);
+    let algebraic = (2.0 * log2_p / (d as f64).log2()).ceil() as usize + 2 * t;
+    Ok(statistical.max(algebraic))
+}
+
+#[cfg(test)]
+mod round_numbers_tests {
+    use super::*;
+    use crate::fields::{
+        babybear::FpBabyBear, bls12::FpBLS12, bn256::FpBN256, goldilocks::FpGoldiLocks,
+    };
+    use crate::gmimc::{
+        gmimc_instance_babybear::*, gmimc_instance_bls12::*, gmimc_instance_bn256::*,
+        gmimc_instance_goldilocks::*, gmimc_instance_pallas::*, gmimc_instance_vesta::*,
+        gmimc_params::GmimcParams,
+    };
+    use crate::neptune::{neptune_instances::*, neptune_params::NeptuneParams};
+    use crate::poseidon::{
+        poseidon_instance_babybear::*, poseidon_instance_bls12::*, poseidon_instance_bn256::*,
+        poseidon_instance_goldilocks::*, poseidon_instance_pallas::*, poseidon_instance_vesta::*,
+        poseidon_params::PoseidonParams,
+    };
+    use crate::poseidon2::{
+        poseidon2_instance_babybear::*, poseidon2_instance_bls12::*, poseidon2_instance_bn256::*,
+        poseidon2_instance_goldilocks::*, poseidon2_instance_pallas::*,
+        poseidon2_instance_vesta::*, poseidon2_params::Poseidon2Params,
+    };
+
+    static SECURITY_LEVEL: usize = 128;
+
+    fn check_poseidon<F: PrimeField>(params: &PoseidonParams<F>) {
+        let (rounds_f, rounds_p) =
+            poseidon_rounds::<F>(params.t, params.d, SECURITY_LEVEL).unwrap();
+        assert!(2 * params.rounds_f_beginning >= rounds_f);
+        assert!(params.rounds_p >= rounds_p);
+    }
+
+    fn check_poseidon2<F: PrimeField>(params: &Poseidon2Params<F>) {
+        let (rounds_f, rounds_p) =
+            poseidon2_rounds::<F>(params.t, params.d, SECURITY_LEVEL).unwrap();
+        assert!(2 * params.rounds_f_beginning >= rounds_f);
+        assert!(params.rounds_p >= rounds_p);
+    }
+
+    fn check_neptune<F: PrimeField>(params: &NeptuneParams<F>) {
+        let (rounds_f, rounds_p) = neptune_rounds::<F>(params.t, params.d, SECURITY_LEVEL).unwrap();
+        assert!(2 * params.rounds_f_beginning >= rounds_f);
+        assert!(params.rounds_p >= rounds_p);
+    }
+
+    fn check_gmimc<F: PrimeField>(params: &GmimcParams<F>) {
+        let rounds = gmimc_rounds::<F>(params.t, params.d, SECURITY_LEVEL).unwrap();
+        assert!(params.rounds >= rounds);
+    }
+
+    #[test]
+    fn reference_values() {
+        // round numbers of the Poseidon2 paper, which the reference script reproduces
+        assert_eq!(poseidon_rounds::<FpBN256>(3, 5, 128), Ok((8, 56)));
+        assert_eq!(poseidon_rounds::<FpBLS12>(8, 5, 128), Ok((8, 57)));
+        assert_eq!(poseidon_rounds::<FpGoldiLocks>(12, 7, 128), Ok((8, 22)));
+        assert_eq!(poseidon_rounds::<FpBabyBear>(16, 7, 128), Ok((8, 13)));
+        assert_eq!(poseidon2_rounds::<FpBabyBear>(24, 7, 128), Ok((8, 21)));
+
+        // t = 4 over 255-bit fields: ceil(1.125 * ceil(60.40)) = 69, not ceil(1.125 * 60.40) = 68
+        assert_eq!(neptune_rounds::<FpBN256>(4, 5, 128), Ok((6, 69)));
+        assert_eq!(neptune_rounds::<FpBLS12>(8, 5, 128), Ok((6, 74)));
+        assert_eq!(neptune_rounds::<FpGoldiLocks>(12, 7, 128), Ok((6, 42)));
+        assert_eq!(neptune_rounds::<FpBabyBear>(24, 7, 128), Ok((6, 43)));
+
+        assert_eq!(gmimc_rounds::<FpBLS12>(2, 5, 128), Ok(224));
+        assert_eq!(gmimc_rounds::<FpGoldiLocks>(8, 7, 128), Ok(146));
+
+        assert_eq!(
+            poseidon_rounds::<FpBabyBear>(16, 5, 128),
+            Err(ParamsError::InvalidSboxDegree(5))
+        );
+        assert_eq!(
+            neptune_rounds::<FpGoldiLocks>(9, 7, 128),
+            Err(ParamsError::InvalidStateSize(9))
+        );
+        // out of reach of the searched R_F < 100 and R_P < 500
+        assert_eq!(
+            poseidon_rounds::<FpBN256>(3, 5, 4096),
+            Err(ParamsError::UnreachableSecurity(4096))
+        );
+    }
+
+    #[test]
+    fn shipped_instances() {
+        check_poseidon(&POSEIDON_BABYBEAR_16_PARAMS);
+        check_poseidon(&POSEIDON_BABYBEAR_24_PARAMS);
+        check_poseidon(&POSEIDON_BLS_2_PARAMS);
+        check_poseidon(&POSEIDON_BLS_3_PARAMS);
+        check_poseidon(&POSEIDON_BLS_4_PARAMS);
+        check_poseidon(&POSEIDON_BLS_8_PARAMS);
+        check_poseidon(&POSEIDON_BN_PARAMS);
+        check_poseidon(&POSEIDON_GOLDILOCKS_8_PARAMS);
+        check_poseidon(&POSEIDON_GOLDILOCKS_12_PARAMS);
+        check_poseidon(&POSEIDON_GOLDILOCKS_16_PARAMS);
+        check_poseidon(&POSEIDON_GOLDILOCKS_20_PARAMS);
+        check_poseidon(&POSEIDON_PALLAS_3_PARAMS);
+        check_poseidon(&POSEIDON_PALLAS_4_PARAMS);
+        check_poseidon(&POSEIDON_PALLAS_8_PARAMS);
+        check_poseidon(&POSEIDON_VESTA_PARAMS);
+
+        check_poseidon2(&POSEIDON2_BABYBEAR_16_PARAMS);
+        check_poseidon2(&POSEIDON2_BABYBEAR_24_PARAMS);
+        check_poseidon2(&POSEIDON2_BLS_2_PARAMS);
+        check_poseidon2(&POSEIDON2_BLS_3_PARAMS);
+        check_poseidon2(&POSEIDON2_BLS_4_PARAMS);
+        check_poseidon2(&POSEIDON2_BLS_8_PARAMS);
+        check_poseidon2(&POSEIDON2_BN256_PARAMS);
+        check_poseidon2(&POSEIDON2_GOLDILOCKS_8_PARAMS);
+        check_poseidon2(&POSEIDON2_GOLDILOCKS_12_PARAMS);
+        check_poseidon2(&POSEIDON2_GOLDILOCKS_16_PARAMS);
+        check_poseidon2(&POSEIDON2_GOLDILOCKS_20_PARAMS);
+        check_poseidon2(&POSEIDON2_PALLAS_3_PARAMS);
+        check_poseidon2(&POSEIDON2_PALLAS_4_PARAMS);
+        check_poseidon2(&POSEIDON2_PALLAS_8_PARAMS);
+        check_poseidon2(&POSEIDON2_VESTA_PARAMS);
+
+        check_neptune(&NEPTUNE_BN_PARAMS);
+        check_neptune(&NEPTUNE_BLS_4_PARAMS);
+        check_neptune(&NEPTUNE_BLS_8_PARAMS);
+        check_neptune(&NEPTUNE_GOLDILOCKS_8_PARAMS);
+        check_neptune(&NEPTUNE_GOLDILOCKS_12_PARAMS);
+        check_neptune(&NEPTUNE_GOLDILOCKS_16_PARAMS);
+        check_neptune(&NEPTUNE_GOLDILOCKS_20_PARAMS);
+        check_neptune(&NEPTUNE_BABYBEAR_16_PARAMS);
+        check_neptune(&NEPTUNE_BABYBEAR_24_PARAMS);
+        check_neptune(&NEPTUNE_PALLAS_4_PARAMS);
+        check_neptune(&NEPTUNE_PALLAS_8_PARAMS);
+        check_neptune(&NEPTUNE_VESTA_PARAMS);
+
+        check_gmimc(&GMIMC_BABYBEAR_16_PARAMS);
+        check_gmimc(&GMIMC_BABYBEAR_24_PARAMS);
+        check_gmimc(&GMIMC_BLS_2_PARAMS);
+        check_gmimc(&GMIMC_BLS_3_PARAMS);
+        check_gmimc(&GMIMC_BLS_4_PARAMS);
+        check_gmimc(&GMIMC_BLS_5_PARAMS);
+        check_gmimc(&GMIMC_BLS_8_PARAMS);
+        check_gmimc(&GMIMC_BLS_9_PARAMS);
+        check_gmimc(&GMIMC_BLS_12_PARAMS);
+        check_gmimc(&GMIMC_BLS_16_PARAMS);
+        check_gmimc(&GMIMC_BLS_20_PARAMS);
+        check_gmimc(&GMIMC_BLS_24_PARAMS);
+        check_gmimc(&GMIMC_BN_3_PARAMS);
+        check_gmimc(&GMIMC_BN_4_PARAMS);
+        check_gmimc(&GMIMC_BN_5_PARAMS);
+        check_gmimc(&GMIMC_BN_8_PARAMS);
+        check_gmimc(&GMIMC_BN_9_PARAMS);
+        check_gmimc(&GMIMC_BN_12_PARAMS);
+        check_gmimc(&GMIMC_BN_16_PARAMS);
+        check_gmimc(&GMIMC_BN_20_PARAMS);
+        check_gmimc(&GMIMC_BN_24_PARAMS);
+        check_gmimc(&GMIMC_GOLDILOCKS_8_PARAMS);
+        check_gmimc(&GMIMC_GOLDILOCKS_12_PARAMS);
+        check_gmimc(&GMIMC_GOLDILOCKS_16_PARAMS);
+        check_gmimc(&GMIMC_GOLDILOCKS_20_PARAMS);
+        check_gmimc(&GMIMC_PALLAS_3_PARAMS);
+        check_gmimc(&GMIMC_PALLAS_4_PARAMS);
+        check_gmimc(&GMIMC_PALLAS_5_PARAMS);
+        check_gmimc(&GMIMC_PALLAS_8_PARAMS);
+        check_gmimc(&GMIMC_PALLAS_9_PARAMS);
+        check_gmimc(&GMIMC_PALLAS_12_PARAMS);
+        check_gmimc(&GMIMC_PALLAS_16_PARAMS);
+        check_gmimc(&GMIMC_PALLAS_20_PARAMS);
+        check_gmimc(&GMIMC_PALLAS_24_PARAMS);
+        check_gmimc(&GMIMC_VESTA_3_PARAMS);
+        check_gmimc(&GMIMC_VESTA_4_PARAMS);
+        check_gmimc(&GMIMC_VESTA_5_PARAMS);
+        check_gmimc(&GMIMC_VESTA_8_PARAMS);
+        check_gmimc(&GMIMC_VESTA_9_PARAMS);
+        check_gmimc(&GMIMC_VESTA_12_PARAMS);
+        check_gmimc(&GMIMC_VESTA_16_PARAMS);
+        check_gmimc(&GMIMC_VESTA_20_PARAMS);
+        check_gmimc(&GMIMC_VESTA_24_PARAMS);
+    }
+}
@@ -0,0 +1,89 @@
+use ark_ff::PrimeField;
+
+use crate::fields::{
+    babybear::FpBabyBear, bls12::FpBLS12, bn256::FpBN256, goldilocks::FpGoldiLocks,
+    pallas::FpPallas, utils::pack_bytes, vesta::FpVesta,
+};
+use crate::poseidon2::{
+    poseidon2::Poseidon2, poseidon2_instance_babybear::POSEIDON2_BABYBEAR_16_PARAMS,
+    poseidon2_instance_bls12::POSEIDON2_BLS_3_PARAMS,
+    poseidon2_instance_bn256::POSEIDON2_BN256_PARAMS,
+    poseidon2_instance_goldilocks::POSEIDON2_GOLDILOCKS_12_PARAMS,
+    poseidon2_instance_pallas::POSEIDON2_PALLAS_3_PARAMS,
+    poseidon2_instance_vesta::POSEIDON2_VESTA_PARAMS,
+};
+use crate::sponge::sponge::{capacity_for, Sponge};
+
+// Fields with a Poseidon2 instance used by hash_bytes
+pub trait BytesHashField: PrimeField {
+    fn bytes_permutation() -> Poseidon2<Self>;
+}
+
+impl BytesHashField for FpBabyBear {
+    fn bytes_permutation() -> Poseidon2<Self> {
+        Poseidon2::new(&POSEIDON2_BABYBEAR_16_PARAMS)
+    }
+}
+
+impl BytesHashField for FpBLS12 {
+    fn bytes_permutation() -> Poseidon2<Self> {
+        Poseidon2::new(&POSEIDON2_BLS_3_PARAMS)
+    }
+}
+
+impl BytesHashField for FpBN256 {
+    fn bytes_permutation() -> Poseidon2<Self> {
+        Poseidon2::new(&POSEIDON2_BN256_PARAMS)
+    }
+}
+
+impl BytesHashField for FpGoldiLocks {
+    fn bytes_permutation() -> Poseidon2<Self> {
+        Poseidon2::new(&POSEIDON2_GOLDILOCKS_12_PARAMS)
+    }
+}
+
+impl BytesHashField for FpPallas {
+    fn bytes_permutation() -> Poseidon2<Self> {
+        Poseidon2::new(&POSEIDON2_PALLAS_3_PARAMS)
+    }
+}
+
+impl BytesHashField for FpVesta {
+    fn bytes_permutation() -> Poseidon2<Self> {
+        Poseidon2::new(&POSEIDON2_VESTA_PARAMS)
+    }
+}
+
+// Hashes a byte string into one field element by absorbing pack_bytes into a Poseidon2
+// sponge whose capacity holds at least 254 bits
+pub fn hash_bytes<F: BytesHashField>(bytes: &[u8]) -> F {
+    let perm = F::bytes_permutation();
+    let rate = perm.get_t() - capacity_for::<F>();
+
+    let mut sponge = Sponge::new(perm, rate).expect("every instance leaves room for the rate");
+    sponge.absorb(&pack_bytes::<F>(bytes));
+    sponge.squeeze(1)[0]
+}
+
+#[cfg(test)]
+mod bytes_tests {
+    use super::*;
+
+    #[test]
+    fn hash_bytes_fields() {
+        fn check<F: BytesHashField>() {
+            let bytes = b"merkle leaf";
+            assert_eq!(hash_bytes::<F>(bytes), hash_bytes::<F>(bytes));
+            assert_ne!(hash_bytes::<F>(bytes), hash_bytes::<F>(&bytes[1..]));
+            assert_ne!(hash_bytes::<F>(&[]), hash_bytes::<F>(&[0]));
+        }
+
+        check::<FpBabyBear>();
+        check::<FpBLS12>();
+        check::<FpBN256>();
+        check::<FpGoldiLocks>();
+        check::<FpPallas>();
+        check::<FpVesta>();
+    }
+}
@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+pub mod sponge;
+pub mod bytes;
+pub mod safe;
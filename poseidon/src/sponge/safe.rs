@@ -0,0 +1,454 @@
+use crate::permutation::Permutation;
+
+use ark_ff::PrimeField;
+use sha3::{Digest, Sha3_256};
+use std::convert::TryFrom;
+use std::fmt;
+
+/// One entry of a SAFE IO pattern.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpongeOp {
+    Absorb(u32),
+    Squeeze(u32),
+}
+
+impl SpongeOp {
+    fn len(&self) -> u32 {
+        match self {
+            SpongeOp::Absorb(len) | SpongeOp::Squeeze(len) => *len,
+        }
+    }
+
+    // 32-bit word of the tag encoding, the MSB marks absorbs
+    fn encode(&self) -> u32 {
+        match self {
+            SpongeOp::Absorb(len) => 0x8000_0000 | len,
+            SpongeOp::Squeeze(len) => *len,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SafeError {
+    InvalidRate,
+    InvalidPattern,
+    CapacityTooSmall,
+    PatternMismatch,
+    IncompletePattern,
+}
+
+impl fmt::Display for SafeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SafeError::InvalidRate => f.write_str("Rate must leave room for the capacity"),
+            SafeError::InvalidPattern => f.write_str("Invalid IO pattern"),
+            SafeError::CapacityTooSmall => f.write_str("Capacity cannot hold the IO pattern tag"),
+            SafeError::PatternMismatch => f.write_str("Sponge call does not match the IO pattern"),
+            SafeError::IncompletePattern => f.write_str("IO pattern was not completed"),
+        }
+    }
+}
+
+impl std::error::Error for SafeError {}
+
+/// Merges consecutive calls of the same kind, e.g. two absorbs of 2 into one absorb of 4.
+pub fn aggregate_pattern(pattern: &[SpongeOp]) -> Result<Vec<SpongeOp>, SafeError> {
+    let mut aggregated: Vec<SpongeOp> = Vec::with_capacity(pattern.len());
+    for op in pattern {
+        if op.len() == 0 || op.len() >= 0x8000_0000 {
+            return Err(SafeError::InvalidPattern);
+        }
+        let merged = match (aggregated.last(), op) {
+            (Some(SpongeOp::Absorb(a)), SpongeOp::Absorb(b)) => Some(SpongeOp::Absorb(a + b)),
+            (Some(SpongeOp::Squeeze(a)), SpongeOp::Squeeze(b)) => Some(SpongeOp::Squeeze(a + b)),
+            _ => None,
+        };
+        match merged {
+            Some(merged) if merged.len() < 0x8000_0000 => *aggregated.last_mut().unwrap() = merged,
+            Some(_) => return Err(SafeError::InvalidPattern),
+            None => aggregated.push(*op),
+        }
+    }
+    if aggregated.is_empty() {
+        return Err(SafeError::InvalidPattern);
+    }
+    Ok(aggregated)
+}
+
+/// 128-bit tag: the first 16 bytes of SHA3-256 over the big-endian words of the
+/// aggregated pattern followed by the domain separator.
+pub fn pattern_tag(pattern: &[SpongeOp], domain_separator: &[u8]) -> Result<[u8; 16], SafeError> {
+    let mut hasher = Sha3_256::new();
+    for op in aggregate_pattern(pattern)? {
+        hasher.update(op.encode().to_be_bytes());
+    }
+    hasher.update(domain_separator);
+
+    let mut tag = [0u8; 16];
+    tag.copy_from_slice(&hasher.finalize()[..16]);
+    Ok(tag)
+}
+
+/// Tag as capacity elements. Fields of more than 128 bits take it in one element,
+/// smaller fields in big-endian chunks of (MODULUS_BIT_SIZE - 1) / 8 bytes.
+///
+/// SAFE leaves this mapping to the implementation, so the chunking is specific to
+/// this crate: other SAFE implementations only agree on fields of more than 128 bits,
+/// and only if they also read the tag as one big-endian integer.
+pub fn tag_elements<F: PrimeField>(tag: &[u8; 16]) -> Vec<F> {
+    let chunk = ((F::MODULUS_BIT_SIZE as usize - 1) / 8).min(16);
+    tag.chunks(chunk).map(F::from_be_bytes_mod_order).collect()
+}
+
+/// Sponge following the SAFE API: the IO pattern and a domain separator are
+/// hashed into the capacity at start, and every call is checked against the
+/// declared pattern.
+///
+/// The state layout is implementation-defined as well: the capacity comes first,
+/// `[capacity | rate]`, with the tag elements at its start, as in [`Sponge`].
+/// Outputs match another SAFE implementation only if it uses the same layout
+/// and the same [`tag_elements`] encoding.
+///
+/// [`Sponge`]: crate::sponge::sponge::Sponge
+#[derive(Clone, Debug)]
+pub struct SafeSponge<F: PrimeField, P: Permutation<F>> {
+    perm: P,
+    state: Vec<F>,
+    capacity: usize,
+    rate: usize,
+    absorb_pos: usize,
+    squeeze_pos: usize,
+    pattern: Vec<SpongeOp>,
+    op_index: usize,
+    op_done: u32,
+}
+
+impl<F: PrimeField, P: Permutation<F>> SafeSponge<F, P> {
+    pub fn start(
+        perm: P,
+        rate: usize,
+        pattern: &[SpongeOp],
+        domain_separator: &[u8],
+    ) -> Result<Self, SafeError> {
+        let t = perm.get_t();
+        if rate == 0 || rate >= t {
+            return Err(SafeError::InvalidRate);
+        }
+
+        let pattern = aggregate_pattern(pattern)?;
+        let tag = tag_elements::<F>(&pattern_tag(&pattern, domain_separator)?);
+        let capacity = t - rate;
+        if tag.len() > capacity {
+            return Err(SafeError::CapacityTooSmall);
+        }
+
+        let mut state = vec![F::zero(); t];
+        state[..tag.len()].copy_from_slice(&tag);
+
+        Ok(SafeSponge {
+            perm,
+            state,
+            capacity,
+            rate,
+            absorb_pos: 0,
+            // the first squeeze always permutes
+            squeeze_pos: rate,
+            pattern,
+            op_index: 0,
+            op_done: 0,
+        })
+    }
+
+    pub fn absorb(&mut self, input: &[F]) -> Result<(), SafeError> {
+        let len = self.call_len(input.len())?;
+        self.consume(SpongeOp::Absorb(len))?;
+
+        for element in input {
+            if self.absorb_pos == self.rate {
+                self.permute();
+                self.absorb_pos = 0;
+            }
+            self.state[self.capacity + self.absorb_pos].add_assign(element);
+            self.absorb_pos += 1;
+        }
+        self.squeeze_pos = self.rate;
+        Ok(())
+    }
+
+    pub fn squeeze(&mut self, n: usize) -> Result<Vec<F>, SafeError> {
+        let len = self.call_len(n)?;
+        self.consume(SpongeOp::Squeeze(len))?;
+
+        let mut output = Vec::with_capacity(n);
+        for _ in 0..n {
+            if self.squeeze_pos == self.rate {
+                self.permute();
+                self.squeeze_pos = 0;
+                self.absorb_pos = 0;
+            }
+            output.push(self.state[self.capacity + self.squeeze_pos]);
+            self.squeeze_pos += 1;
+        }
+        Ok(output)
+    }
+
+    /// Checks that the whole pattern was used and erases the state.
+    pub fn finish(mut self) -> Result<(), SafeError> {
+        let complete = self.op_index == self.pattern.len();
+        self.erase();
+        if !complete {
+            return Err(SafeError::IncompletePattern);
+        }
+        Ok(())
+    }
+
+    // a call longer than u32::MAX cannot match any pattern entry
+    fn call_len(&mut self, len: usize) -> Result<u32, SafeError> {
+        u32::try_from(len).map_err(|_| {
+            self.erase();
+            SafeError::PatternMismatch
+        })
+    }
+
+    // advances the pattern by one call, erasing the state on mismatch
+    fn consume(&mut self, call: SpongeOp) -> Result<(), SafeError> {
+        if call.len() == 0 {
+            return Ok(());
+        }
+        let expected = match self.pattern.get(self.op_index) {
+            Some(op) => *op,
+            None => {
+                self.erase();
+                return Err(SafeError::PatternMismatch);
+            }
+        };
+        let same_kind = matches!(
+            (expected, call),
+            (SpongeOp::Absorb(_), SpongeOp::Absorb(_))
+                | (SpongeOp::Squeeze(_), SpongeOp::Squeeze(_))
+        );
+        if !same_kind || call.len() > expected.len() - self.op_done {
+            self.erase();
+            return Err(SafeError::PatternMismatch);
+        }
+
+        self.op_done += call.len();
+        if self.op_done == expected.len() {
+            self.op_index += 1;
+            self.op_done = 0;
+        }
+        Ok(())
+    }
+
+    fn erase(&mut self) {
+        for element in self.state.iter_mut() {
+            *element = F::zero();
+        }
+        self.op_index = self.pattern.len() + 1;
+    }
+
+    fn permute(&mut self) {
+        self.state = self.perm.permutation(&self.state);
+    }
+}
+
+// Only bn256_kat is a known-answer test. The others check the tag against SHA3-256 and
+// the sponge against direct permutation calls, so they do not pin outputs across versions.
+#[cfg(test)]
+mod safe_tests {
+    use super::*;
+    use crate::fields::{
+        babybear::FpBabyBear, bn256::FpBN256, goldilocks::FpGoldiLocks, utils::from_hex,
+    };
+    use crate::poseidon2::{
+        poseidon2::Poseidon2, poseidon2_instance_babybear::POSEIDON2_BABYBEAR_16_PARAMS,
+        poseidon2_instance_bn256::POSEIDON2_BN256_PARAMS,
+        poseidon2_instance_goldilocks::POSEIDON2_GOLDILOCKS_12_PARAMS,
+    };
+
+    type Scalar = FpBN256;
+
+    #[test]
+    fn tag_encoding() {
+        // arity 4 Merkle tree node: ABSORB(4), SQUEEZE(1)
+        // expected tag from an independent SHA3-256 (Python hashlib) over
+        // 80000004 00000001 || "merkle"
+        let pattern = [SpongeOp::Absorb(4), SpongeOp::Squeeze(1)];
+        let tag = pattern_tag(&pattern, b"merkle").unwrap();
+        assert_eq!(
+            tag,
+            [
+                0xd4, 0x7d, 0xb0, 0x3b, 0x2c, 0x07, 0x4c, 0x9d, 0xd4, 0xe0, 0xb3, 0x8c, 0x20, 0x1b,
+                0xec, 0xab
+            ]
+        );
+        assert_eq!(
+            tag_elements::<Scalar>(&tag),
+            vec![Scalar::from(0xd47db03b2c074c9dd4e0b38c201becabu128)]
+        );
+        assert_eq!(
+            tag_elements::<FpGoldiLocks>(&tag),
+            vec![
+                FpGoldiLocks::from(0xd47db03b2c074cu64),
+                FpGoldiLocks::from(0x9dd4e0b38c201bu64),
+                FpGoldiLocks::from(0xecabu64),
+            ]
+        );
+
+        // consecutive calls of the same kind are aggregated before hashing
+        let split = [
+            SpongeOp::Absorb(1),
+            SpongeOp::Absorb(3),
+            SpongeOp::Squeeze(1),
+        ];
+        assert_eq!(
+            pattern_tag(&split, b"merkle"),
+            pattern_tag(&pattern, b"merkle")
+        );
+        assert_ne!(
+            pattern_tag(&pattern, b"other"),
+            pattern_tag(&pattern, b"merkle")
+        );
+        assert_ne!(
+            pattern_tag(&[SpongeOp::Absorb(4), SpongeOp::Squeeze(2)], b"merkle"),
+            pattern_tag(&pattern, b"merkle")
+        );
+
+        assert_eq!(aggregate_pattern(&[]), Err(SafeError::InvalidPattern));
+        assert_eq!(
+            aggregate_pattern(&[SpongeOp::Absorb(0)]),
+            Err(SafeError::InvalidPattern)
+        );
+        assert_eq!(tag_elements::<Scalar>(&[1; 16]).len(), 1);
+        assert_eq!(tag_elements::<FpGoldiLocks>(&[1; 16]).len(), 3);
+        assert_eq!(tag_elements::<FpBabyBear>(&[1; 16]).len(), 6);
+    }
+
+    #[test]
+    fn io_pattern_checks() {
+        let perm = Poseidon2::new(&POSEIDON2_BN256_PARAMS);
+        let pattern = [SpongeOp::Absorb(3), SpongeOp::Squeeze(2)];
+        let input: Vec<Scalar> = (0..3).map(|i| Scalar::from(i as u64)).collect();
+
+        let mut sponge = SafeSponge::start(perm.clone(), 2, &pattern, b"test").unwrap();
+        sponge.absorb(&input[..1]).unwrap();
+        sponge.absorb(&input[1..]).unwrap();
+        let output = sponge.squeeze(2).unwrap();
+        sponge.finish().unwrap();
+
+        // tag in the capacity, then one permutation per started rate block
+        let tag = tag_elements::<Scalar>(&pattern_tag(&pattern, b"test").unwrap());
+        let state = perm.permutation(&[tag[0], input[0], input[1]]);
+        let state = perm.permutation(&[state[0], state[1] + input[2], state[2]]);
+        assert_eq!(output, state[1..].to_vec());
+
+        // calls that differ from the pattern are refused
+        let mut sponge = SafeSponge::start(perm.clone(), 2, &pattern, b"test").unwrap();
+        assert_eq!(sponge.squeeze(1), Err(SafeError::PatternMismatch));
+        assert_eq!(sponge.absorb(&input), Err(SafeError::PatternMismatch));
+
+        let mut sponge = SafeSponge::start(perm.clone(), 2, &pattern, b"test").unwrap();
+        assert_eq!(
+            sponge.absorb(&[input.clone(), input.clone()].concat()),
+            Err(SafeError::PatternMismatch)
+        );
+
+        let mut sponge = SafeSponge::start(perm.clone(), 2, &pattern, b"test").unwrap();
+        sponge.absorb(&input).unwrap();
+        sponge.squeeze(1).unwrap();
+        assert_eq!(sponge.finish(), Err(SafeError::IncompletePattern));
+
+        let mut sponge = SafeSponge::start(perm.clone(), 2, &pattern, b"test").unwrap();
+        sponge.absorb(&input).unwrap();
+        sponge.squeeze(2).unwrap();
+        assert_eq!(sponge.squeeze(1), Err(SafeError::PatternMismatch));
+
+        // lengths beyond u32 are refused before anything is allocated
+        let mut sponge = SafeSponge::start(perm, 2, &pattern, b"test").unwrap();
+        sponge.absorb(&input).unwrap();
+        assert_eq!(
+            sponge.squeeze(u32::MAX as usize + 2),
+            Err(SafeError::PatternMismatch)
+        );
+    }
+
+    #[test]
+    fn bn256_kat() {
+        // Expected outputs from an independent Python reimplementation of SAFE and of
+        // Poseidon2 t = 3 (constants read from poseidon2_instance_bn256.rs), with the same
+        // tag encoding and [capacity | rate] layout. They are not published test vectors.
+        let perm = Poseidon2::new(&POSEIDON2_BN256_PARAMS);
+
+        let pattern = [SpongeOp::Absorb(4), SpongeOp::Squeeze(1)];
+        let input: Vec<Scalar> = (1..=4).map(|i| Scalar::from(i as u64)).collect();
+        let mut sponge = SafeSponge::start(perm.clone(), 2, &pattern, b"merkle").unwrap();
+        sponge.absorb(&input).unwrap();
+        assert_eq!(
+            sponge.squeeze(1).unwrap(),
+            vec![from_hex(
+                "0x0072b7af16759a47fc384ce9685635c961f0ccb9d6e0fdfbf1b7ee5b38e646e5"
+            )]
+        );
+        sponge.finish().unwrap();
+
+        let pattern = [SpongeOp::Absorb(5), SpongeOp::Squeeze(3)];
+        let input: Vec<Scalar> = (0..5).map(|i| Scalar::from(i as u64)).collect();
+        let mut sponge = SafeSponge::start(perm, 2, &pattern, b"").unwrap();
+        sponge.absorb(&input[..2]).unwrap();
+        sponge.absorb(&input[2..]).unwrap();
+        let mut output = sponge.squeeze(1).unwrap();
+        output.extend(sponge.squeeze(2).unwrap());
+        assert_eq!(
+            output,
+            vec![
+                from_hex("0x116a3da7633bf97ddc4e110a6faf9342bf897db1f225e3cc11f068732c66fa67"),
+                from_hex("0x0e19bd7b729649f1c620e0ead69bd49ee1a704520a911fce38aafe249e11a8e0"),
+                from_hex("0x0eb46699e99742fce1d29411b407cf10a371d448f6d645bd87bfcd140dcac07d"),
+            ]
+        );
+        sponge.finish().unwrap();
+    }
+
+    // absorbs 0..10 with rate 8 and squeezes 4, against the same steps done by hand
+    fn check_small_field<F: PrimeField, P: Permutation<F> + Clone>(perm: P, tag_len: usize) {
+        let pattern = [SpongeOp::Absorb(10), SpongeOp::Squeeze(4)];
+        let input: Vec<F> = (0..10).map(|i| F::from(i as u64)).collect();
+        let t = perm.get_t();
+        let capacity = t - 8;
+
+        let mut sponge = SafeSponge::start(perm.clone(), 8, &pattern, b"").unwrap();
+        sponge.absorb(&input).unwrap();
+        let output = sponge.squeeze(4).unwrap();
+        sponge.finish().unwrap();
+
+        let tag = tag_elements::<F>(&pattern_tag(&pattern, b"").unwrap());
+        assert_eq!(tag.len(), tag_len);
+        let mut state = vec![F::zero(); t];
+        state[..tag_len].copy_from_slice(&tag);
+        state[capacity..].copy_from_slice(&input[..8]);
+        let mut state = perm.permutation(&state);
+        state[capacity].add_assign(&input[8]);
+        state[capacity + 1].add_assign(&input[9]);
+        let state = perm.permutation(&state);
+        assert_eq!(output, state[capacity..capacity + 4].to_vec());
+    }
+
+    #[test]
+    fn small_fields() {
+        check_small_field(Poseidon2::new(&POSEIDON2_GOLDILOCKS_12_PARAMS), 3);
+        check_small_field(Poseidon2::new(&POSEIDON2_BABYBEAR_16_PARAMS), 6);
+
+        let pattern = [SpongeOp::Absorb(10), SpongeOp::Squeeze(4)];
+        let perm = Poseidon2::new(&POSEIDON2_BABYBEAR_16_PARAMS);
+        assert_eq!(
+            SafeSponge::start(perm.clone(), 12, &pattern, b"").err(),
+            Some(SafeError::CapacityTooSmall)
+        );
+        for rate in [0, 16] {
+            assert_eq!(
+                SafeSponge::start(perm.clone(), rate, &pattern, b"").err(),
+                Some(SafeError::InvalidRate)
+            );
+        }
+    }
+}
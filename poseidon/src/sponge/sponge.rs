@@ -0,0 +1,215 @@
+use crate::permutation::Permutation;
+use crate::utils::ParamsError;
+
+use ark_ff::PrimeField;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SpongeMode {
+    Absorbing,
+    Squeezing,
+}
+
+/// Duplex sponge over any permutation of width t = capacity + rate.
+///
+/// The state is laid out as `[capacity | rate]`. Inputs are added into the rate
+/// part, and every absorb phase is closed with 10* padding (a one followed by
+/// zeros up to the block boundary) before the first squeeze, so inputs of
+/// different lengths never collide.
+#[derive(Clone, Debug)]
+pub struct Sponge<F: PrimeField, P: Permutation<F>> {
+    perm: P,
+    state: Vec<F>,
+    capacity: usize,
+    rate: usize,
+    pos: usize,
+    mode: SpongeMode,
+}
+
+impl<F: PrimeField, P: Permutation<F>> Sponge<F, P> {
+    /// Fails unless 0 < rate < t, i.e. both the rate and the capacity are non-empty.
+    pub fn new(perm: P, rate: usize) -> Result<Self, ParamsError> {
+        let t = perm.get_t();
+        if rate == 0 || rate >= t {
+            return Err(ParamsError::InvalidRate { rate, t });
+        }
+
+        Ok(Sponge {
+            perm,
+            state: vec![F::zero(); t],
+            capacity: t - rate,
+            rate,
+            pos: 0,
+            mode: SpongeMode::Absorbing,
+        })
+    }
+
+    pub fn get_rate(&self) -> usize {
+        self.rate
+    }
+
+    pub fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn absorb(&mut self, input: &[F]) {
+        if self.mode == SpongeMode::Squeezing {
+            // duplexing: start a new absorb phase on top of the squeezed state
+            self.mode = SpongeMode::Absorbing;
+            self.pos = 0;
+        }
+
+        for element in input {
+            if self.pos == self.rate {
+                self.permute();
+            }
+            self.state[self.capacity + self.pos].add_assign(element);
+            self.pos += 1;
+        }
+    }
+
+    pub fn squeeze(&mut self, n: usize) -> Vec<F> {
+        if self.mode == SpongeMode::Absorbing {
+            self.pad();
+            self.permute();
+            self.mode = SpongeMode::Squeezing;
+        }
+
+        let mut output = Vec::with_capacity(n);
+        for _ in 0..n {
+            if self.pos == self.rate {
+                self.permute();
+            }
+            output.push(self.state[self.capacity + self.pos]);
+            self.pos += 1;
+        }
+        output
+    }
+
+    // 10* padding, always applied so that a full last block is separated as well
+    fn pad(&mut self) {
+        if self.pos == self.rate {
+            self.permute();
+        }
+        self.state[self.capacity + self.pos].add_assign(&F::one());
+    }
+
+    fn permute(&mut self) {
+        self.state = self.perm.permutation(&self.state);
+        self.pos = 0;
+    }
+}
+
+/// Number of elements of F needed for a capacity of at least 254 bits: one element
+/// of the 254/255-bit fields, four Goldilocks or nine BabyBear elements.
+pub fn capacity_for<F: PrimeField>() -> usize {
+    (2 * 127usize).div_ceil(F::MODULUS_BIT_SIZE as usize)
+}
+
+#[cfg(test)]
+mod sponge_tests {
+    use super::*;
+    use crate::fields::{
+        babybear::FpBabyBear, bls12::FpBLS12, bn256::FpBN256, goldilocks::FpGoldiLocks,
+        utils::random_scalar,
+    };
+    use crate::gmimc::{gmimc::Gmimc, gmimc_instance_bn256::GMIMC_BN_3_PARAMS};
+    use crate::neptune::{neptune::Neptune, neptune_instances::NEPTUNE_BN_PARAMS};
+    use crate::poseidon::{poseidon::Poseidon, poseidon_instance_bn256::POSEIDON_BN_PARAMS};
+    use crate::poseidon2::{
+        poseidon2::Poseidon2, poseidon2_instance_bn256::POSEIDON2_BN256_PARAMS,
+        poseidon2_instance_goldilocks::POSEIDON2_GOLDILOCKS_12_PARAMS,
+    };
+
+    type Scalar = FpBN256;
+
+    fn hash<P: Permutation<Scalar> + Clone>(
+        perm: &P,
+        rate: usize,
+        input: &[Scalar],
+    ) -> Vec<Scalar> {
+        let mut sponge = Sponge::new(perm.clone(), rate).unwrap();
+        sponge.absorb(input);
+        sponge.squeeze(3)
+    }
+
+    fn check_perm<P: Permutation<Scalar> + Clone>(perm: P) {
+        let rate = perm.get_t() - 1;
+        let input: Vec<Scalar> = (0..2 * rate + 1).map(|_| random_scalar()).collect();
+        let digest = hash(&perm, rate, &input);
+
+        // absorbing in pieces is the same as absorbing at once
+        let mut sponge = Sponge::new(perm.clone(), rate).unwrap();
+        for element in input.iter() {
+            sponge.absorb(&[*element]);
+        }
+        let mut output = sponge.squeeze(1);
+        output.extend(sponge.squeeze(2));
+        assert_eq!(output, digest);
+
+        // trailing zeros and block aligned lengths are domain separated
+        for len in 0..=2 * rate {
+            let mut longer = input[..len].to_vec();
+            longer.push(Scalar::from(0u64));
+            assert_ne!(hash(&perm, rate, &input[..len]), hash(&perm, rate, &longer));
+        }
+        let mut padded = input[..rate].to_vec();
+        padded.push(Scalar::from(1u64));
+        assert_ne!(
+            hash(&perm, rate, &input[..rate]),
+            hash(&perm, rate, &padded)
+        );
+    }
+
+    #[test]
+    fn variable_length() {
+        check_perm(Poseidon::new(&POSEIDON_BN_PARAMS));
+        check_perm(Poseidon2::new(&POSEIDON2_BN256_PARAMS));
+        check_perm(Gmimc::new(&GMIMC_BN_3_PARAMS));
+        check_perm(Neptune::new(&NEPTUNE_BN_PARAMS));
+    }
+
+    #[test]
+    fn invalid_rate() {
+        let perm = Poseidon2::new(&POSEIDON2_BN256_PARAMS);
+        for rate in [0, 3, 4] {
+            assert_eq!(
+                Sponge::new(perm.clone(), rate).unwrap_err(),
+                ParamsError::InvalidRate { rate, t: 3 }
+            );
+        }
+    }
+
+    #[test]
+    fn capacities() {
+        assert_eq!(capacity_for::<FpBN256>(), 1);
+        assert_eq!(capacity_for::<FpBLS12>(), 1);
+        assert_eq!(capacity_for::<FpGoldiLocks>(), 4);
+        assert_eq!(capacity_for::<FpBabyBear>(), 9);
+    }
+
+    #[test]
+    fn kats() {
+        // the empty input is the permutation of the padding block
+        let perm = Poseidon2::new(&POSEIDON2_BN256_PARAMS);
+        let mut sponge = Sponge::new(perm.clone(), 2).unwrap();
+        let expected =
+            perm.permutation(&[Scalar::from(0u64), Scalar::from(1u64), Scalar::from(0u64)]);
+        assert_eq!(sponge.squeeze(2), expected[1..].to_vec());
+
+        // squeezing past the rate permutes again
+        let perm = Poseidon2::new(&POSEIDON2_GOLDILOCKS_12_PARAMS);
+        let mut sponge = Sponge::new(perm.clone(), 8).unwrap();
+        assert_eq!(sponge.get_capacity(), 4);
+        let input: Vec<FpGoldiLocks> = (0..3).map(|i| FpGoldiLocks::from(i as u64)).collect();
+        sponge.absorb(&input);
+        let output = sponge.squeeze(10);
+
+        let mut state = vec![FpGoldiLocks::from(0u64); 12];
+        state[4..7].copy_from_slice(&input);
+        state[7] = FpGoldiLocks::from(1u64);
+        let state = perm.permutation(&state);
+        assert_eq!(output[..8], state[4..]);
+        let state = perm.permutation(&state);
+        assert_eq!(output[8..], state[4..6]);
+    }
+}
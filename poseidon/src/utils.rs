@@ -1,11 +1,142 @@
 // use std::cmp::min;
 
 use ark_ff::PrimeField;
+use std::fmt;
+
+/// Error returned when instantiating a permutation with invalid parameters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParamsError {
+    InvalidSboxDegree(usize),
+    InvalidStateSize(usize),
+    InvalidRate {
+        rate: usize,
+        t: usize,
+    },
+    OddFullRounds(usize),
+    ZeroPartialRounds,
+    ZeroRounds,
+    UnreachableSecurity(usize),
+    DimensionMismatch {
+        what: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    GrainOverflow {
+        what: &'static str,
+        value: usize,
+        bits: usize,
+    },
+}
+
+impl fmt::Display for ParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamsError::InvalidSboxDegree(d) => {
+                write!(f, "x^{} is not a permutation of the field", d)
+            }
+            ParamsError::InvalidStateSize(t) => write!(f, "Unsupported state size {}", t),
+            ParamsError::InvalidRate { rate, t } => {
+                write!(f, "Rate {} does not fit a state of size {}", rate, t)
+            }
+            ParamsError::OddFullRounds(r) => {
+                write!(f, "Number of full rounds must be even, got {}", r)
+            }
+            ParamsError::ZeroPartialRounds => f.write_str("Number of partial rounds is zero"),
+            ParamsError::ZeroRounds => f.write_str("Number of rounds is zero"),
+            ParamsError::UnreachableSecurity(level) => {
+                write!(f, "No round numbers in range reach {} bits of security", level)
+            }
+            ParamsError::DimensionMismatch {
+                what,
+                expected,
+                found,
+            } => write!(f, "Expected {} of size {}, found {}", what, expected, found),
+            ParamsError::GrainOverflow { what, value, bits } => write!(
+                f,
+                "{} = {} does not fit the {}-bit field of the Grain LFSR seed",
+                what, value, bits
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParamsError {}
+
+// Checks shared by the SPN constructions: a bijective S-box, R_F split evenly
+// between the beginning and the end, and at least one partial round
+pub(crate) fn check_rounds<F: PrimeField>(
+    d: usize,
+    rounds_f: usize,
+    rounds_p: usize,
+) -> Result<(), ParamsError> {
+    if !is_sbox_permutation::<F>(d) {
+        return Err(ParamsError::InvalidSboxDegree(d));
+    }
+    if rounds_f % 2 == 1 {
+        return Err(ParamsError::OddFullRounds(rounds_f));
+    }
+    if rounds_p == 0 {
+        return Err(ParamsError::ZeroPartialRounds);
+    }
+    Ok(())
+}
+
+pub(crate) fn check_dimension(
+    what: &'static str,
+    expected: usize,
+    found: usize,
+) -> Result<(), ParamsError> {
+    if expected != found {
+        return Err(ParamsError::DimensionMismatch {
+            what,
+            expected,
+            found,
+        });
+    }
+    Ok(())
+}
+
+// Checks an n x m matrix given as a list of rows
+pub(crate) fn check_matrix<F>(
+    what: &'static str,
+    mat: &[Vec<F>],
+    rows: usize,
+    cols: usize,
+) -> Result<(), ParamsError> {
+    check_dimension(what, rows, mat.len())?;
+    mat.iter()
+        .try_for_each(|row| check_dimension(what, cols, row.len()))
+}
 
 // pub fn from_u64<F: PrimeField>(val: u64) -> F {
 //     F::from_repr(F::Repr::from(val)).unwrap()
 // }
 
+// x^d is a permutation of F iff gcd(d, p - 1) = 1; d = 1 is excluded as it is linear
+pub fn is_sbox_permutation<F: PrimeField>(d: usize) -> bool {
+    if d < 2 {
+        return false;
+    }
+
+    // p mod d, limb by limb from the most significant one
+    let d_ = d as u128;
+    let p_mod_d = F::MODULUS
+        .as_ref()
+        .iter()
+        .rev()
+        .fold(0u128, |acc, limb| ((acc << 64) | *limb as u128) % d_);
+    gcd(d_, (p_mod_d + d_ - 1) % d_) == 1
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        let tmp = a % b;
+        a = b;
+        b = tmp;
+    }
+    a
+}
+
 // guassian elimination
 pub fn mat_inverse<F: PrimeField>(mat: &[Vec<F>]) -> Vec<Vec<F>> {
     let n = mat.len();
@@ -103,3 +234,47 @@ pub fn mat_transpose<F: PrimeField>(mat: &[Vec<F>]) -> Vec<Vec<F>> {
     }
     transpose
 }
+
+// checks that every square submatrix is non-singular, exhaustively, so only for small matrices
+pub fn is_mds<F: PrimeField>(mat: &[Vec<F>]) -> bool {
+    let n = mat.len();
+    assert!(n < usize::BITS as usize && mat.iter().all(|row| row.len() == n));
+
+    let subsets: Vec<Vec<usize>> = (1usize..1 << n)
+        .map(|mask| (0..n).filter(|i| mask >> i & 1 == 1).collect())
+        .collect();
+    for rows in subsets.iter() {
+        for cols in subsets.iter().filter(|cols| cols.len() == rows.len()) {
+            let sub: Vec<Vec<F>> = rows
+                .iter()
+                .map(|row| cols.iter().map(|col| mat[*row][*col]).collect())
+                .collect();
+            if !is_non_singular(sub) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+// gaussian elimination with pivot search
+fn is_non_singular<F: PrimeField>(mut m: Vec<Vec<F>>) -> bool {
+    let n = m.len();
+    for col in 0..n {
+        let pivot = match (col..n).find(|row| !m[*row][col].is_zero()) {
+            Some(pivot) => pivot,
+            None => return false,
+        };
+        m.swap(col, pivot);
+        let inv = m[col][col].inverse().unwrap();
+        let (upper, lower) = m.split_at_mut(col + 1);
+        for row in lower.iter_mut() {
+            let factor = row[col] * inv;
+            for (el, pivot_el) in row[col..].iter_mut().zip(upper[col][col..].iter()) {
+                let tmp = *pivot_el * factor;
+                el.sub_assign(&tmp);
+            }
+        }
+    }
+    true
+}
@@ -5,4 +5,82 @@ pub trait HashFunction<F: PrimeField> {
     fn hash(&self, left: &F, right: &F) -> anyhow::Result<Vec<F>>;
     fn zero(&self) -> F;
     fn pad(&self, ileft: &F, right: &F) -> Vec<F>;
+
+    /// Number of children hashed into one node, 2 for binary trees.
+    fn arity(&self) -> usize {
+        2
+    }
+
+    /// Position of the node value in the output of `hash` and `hash_children`.
+    fn output_index(&self) -> usize {
+        1
+    }
+
+    /// Hashes `arity()` children at once. Binary hash functions only accept two.
+    fn hash_children(&self, children: &[F]) -> anyhow::Result<Vec<F>> {
+        match children {
+            [left, right] => self.hash(left, right),
+            _ => Err(anyhow::anyhow!(
+                "Invalid number of children, expected {}",
+                self.arity()
+            )),
+        }
+    }
+
+    /// Node value of two children, the output element selected by `output_index()`.
+    fn digest(&self, left: &F, right: &F) -> anyhow::Result<F> {
+        select_output(self.hash(left, right)?, self.output_index())
+    }
+
+    /// Node value of `arity()` children, the output element selected by `output_index()`.
+    fn digest_children(&self, children: &[F]) -> anyhow::Result<F> {
+        select_output(self.hash_children(children)?, self.output_index())
+    }
+}
+
+/// Compresses two digests of `N` field elements into one. Used on small fields,
+/// where a single element is too short for a collision resistant digest.
+pub trait DigestFunction<F: PrimeField, const N: usize> {
+    fn compress(&self, left: &[F; N], right: &[F; N]) -> anyhow::Result<[F; N]>;
+
+    /// Digest of an empty leaf.
+    fn zero(&self) -> [F; N] {
+        [F::zero(); N]
+    }
+}
+
+/// Hashes the children of a Merkle tree node into the node, for nodes of type `T`.
+/// Every `HashFunction<F>` hashes single elements, digest hash functions hash `[F; N]`.
+pub trait NodeHash<T> {
+    /// Number of children per node.
+    fn node_arity(&self) -> usize;
+
+    /// Value of an empty leaf.
+    fn zero_node(&self) -> T;
+
+    fn hash_nodes(&self, children: &[T]) -> anyhow::Result<T>;
+}
+
+impl<F: PrimeField, H: HashFunction<F>> NodeHash<F> for H {
+    fn node_arity(&self) -> usize {
+        self.arity()
+    }
+
+    fn zero_node(&self) -> F {
+        self.zero()
+    }
+
+    fn hash_nodes(&self, children: &[F]) -> anyhow::Result<F> {
+        self.digest_children(children)
+    }
+}
+
+fn select_output<F: PrimeField>(output: Vec<F>, index: usize) -> anyhow::Result<F> {
+    output.get(index).copied().ok_or_else(|| {
+        anyhow::anyhow!(
+            "Output index {} is out of range for a hash output of {} elements",
+            index,
+            output.len()
+        )
+    })
 }
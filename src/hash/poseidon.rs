@@ -1,77 +1,186 @@
 use ark_ff::PrimeField;
-use serde::de::DeserializeOwned;
+use std::{marker::PhantomData, sync::Arc};
 use zkhash::{
     fields::{
+        babybear::FpBabyBear,
+        bls12::FpBLS12,
         bn256::FpBN256,
         goldilocks::FpGoldiLocks,
-        utils::{decode_from_cbor_string, encode_to_cbor_string, from_hex, random_scalar, to_hex},
+        pallas::FpPallas,
+        utils::{
+            encode_to_cbor_string, random_scalar, to_hex, try_decode_from_cbor_string_strict,
+            try_from_hex_strict,
+        },
         vesta::FpVesta,
     },
+    permutation::{Permutation, PermutationKind},
+    poseidon::{
+        poseidon::Poseidon,
+        poseidon_instance_babybear::{POSEIDON_BABYBEAR_16_PARAMS, POSEIDON_BABYBEAR_24_PARAMS},
+        poseidon_instance_bls12::{
+            POSEIDON_BLS_2_PARAMS, POSEIDON_BLS_3_PARAMS, POSEIDON_BLS_4_PARAMS,
+            POSEIDON_BLS_8_PARAMS,
+        },
+        poseidon_instance_bn256::POSEIDON_BN_PARAMS,
+        poseidon_instance_goldilocks::{
+            POSEIDON_GOLDILOCKS_12_PARAMS, POSEIDON_GOLDILOCKS_16_PARAMS,
+            POSEIDON_GOLDILOCKS_20_PARAMS, POSEIDON_GOLDILOCKS_8_PARAMS,
+        },
+        poseidon_instance_pallas::{
+            POSEIDON_PALLAS_3_PARAMS, POSEIDON_PALLAS_4_PARAMS, POSEIDON_PALLAS_8_PARAMS,
+        },
+        poseidon_instance_vesta::POSEIDON_VESTA_PARAMS,
+        poseidon_params::PoseidonParams,
+    },
     poseidon2::{
         poseidon2::Poseidon2,
+        poseidon2_instance_babybear::{POSEIDON2_BABYBEAR_16_PARAMS, POSEIDON2_BABYBEAR_24_PARAMS},
+        poseidon2_instance_bls12::{
+            POSEIDON2_BLS_2_PARAMS, POSEIDON2_BLS_3_PARAMS, POSEIDON2_BLS_4_PARAMS,
+            POSEIDON2_BLS_8_PARAMS,
+        },
         poseidon2_instance_bn256::POSEIDON2_BN256_PARAMS,
         poseidon2_instance_goldilocks::{
             POSEIDON2_GOLDILOCKS_12_PARAMS, POSEIDON2_GOLDILOCKS_16_PARAMS,
             POSEIDON2_GOLDILOCKS_20_PARAMS, POSEIDON2_GOLDILOCKS_8_PARAMS,
         },
+        poseidon2_instance_pallas::{
+            POSEIDON2_PALLAS_3_PARAMS, POSEIDON2_PALLAS_4_PARAMS, POSEIDON2_PALLAS_8_PARAMS,
+        },
         poseidon2_instance_vesta::POSEIDON2_VESTA_PARAMS,
+        poseidon2_params::Poseidon2Params,
     },
+    sponge::sponge::{capacity_for, Sponge},
 };
 
-// Poseidon hash
-pub struct PoseidonHash<F: PrimeField> {
+/// Padding function, called with the inner children, the state size and the outer children.
+pub type SummaryFn<F> = fn(&[F], usize, left: &F, right: &F) -> Vec<F>;
+
+// Poseidon hash, over Poseidon2 unless another permutation is given
+pub struct PoseidonHash<F: PrimeField, P: Permutation<F> = Poseidon2<F>> {
     pub method: PoseidonMethod,
     // padding input with
     pub summary: Vec<F>,
     // padding function
-    pub summary_fn: Option<fn(&[F], usize, left: &F, right: &F) -> Vec<F>>,
+    pub summary_fn: Option<SummaryFn<F>>,
+    // number of children per tree node
+    pub arity: usize,
+    // how node values are derived from the permutation
+    pub mode: HashMode,
+    // position of the node value in the hash output
+    pub output_index: usize,
     // hasher
-    pub hasher: Poseidon2<F>,
+    pub hasher: P,
+}
+
+/// How a node value is derived from the permutation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashMode {
+    // permutation output of the padded input
+    Permutation,
+    // feed-forward compression Trunc(P(x) + x) of x = children ++ zeros, keeping `outputs` elements
+    Compression { outputs: usize },
 }
 
-impl<F: PrimeField> super::HashFunction<F> for PoseidonHash<F> {
+impl<F: PrimeField, P: Permutation<F>> super::HashFunction<F> for PoseidonHash<F, P> {
     // impl<F: PrimeField> PoseidonHash<F> {
     fn zero(&self) -> F {
         F::zero()
     }
 
     fn pad(&self, left: &F, right: &F) -> Vec<F> {
-        if let Some(summary_fn) = self.summary_fn {
-            summary_fn(&[], self.method.statesize(), left, right)
-        } else {
-            let mut padding = self.summary.clone();
-            padding.push(left.to_owned());
-            padding.push(right.to_owned());
-            padding
-        }
+        self.pad_children(&[left.to_owned(), right.to_owned()])
     }
 
     fn hash(&self, left: &F, right: &F) -> anyhow::Result<Vec<F>> {
-        let input = self.pad(left, right);
-        Ok(self.hasher.permutation(&input))
+        self.hash_children(&[left.to_owned(), right.to_owned()])
+    }
+
+    fn arity(&self) -> usize {
+        self.arity
+    }
+
+    fn hash_children(&self, children: &[F]) -> anyhow::Result<Vec<F>> {
+        if children.len() != self.arity {
+            return Err(anyhow::anyhow!(
+                "Poseidon hash expects {} children per node",
+                self.arity
+            ));
+        }
+        let input = self.pad_children(children);
+        match self.mode {
+            HashMode::Permutation => Ok(self.hasher.permutation(&input)),
+            HashMode::Compression { outputs } => Ok(self.feed_forward(&input, outputs)),
+        }
+    }
+
+    fn output_index(&self) -> usize {
+        self.output_index
     }
 }
 
-impl<F: PrimeField> PoseidonHash<F> {
+impl<F: PrimeField, P: Permutation<F>> PoseidonHash<F, P> {
     pub fn new_for_bintree(
         summary: Option<Vec<F>>,
         rand: bool,
-        summary_fn: Option<fn(&[F], usize, left: &F, right: &F) -> Vec<F>>,
-        hasher: PoseidonHasher<F>,
+        summary_fn: Option<SummaryFn<F>>,
+        hasher: PoseidonHasher<F, P>,
+    ) -> anyhow::Result<Self> {
+        Self::new_for_tree(2, summary, rand, summary_fn, hasher)
+    }
+
+    /// Creates the binary tree hash of `zkhash::merkle_tree::merkle_tree_fp`: each node
+    /// permutes `[left, right, 0, ..]` and keeps the first element. Roots agree with that
+    /// tree over Poseidon2 with a width 3 state for power-of-two leaf counts; it pads other
+    /// leaf sets with the last leaf, where `MerkleTree` pads with zeros.
+    pub fn new_for_zkhash_bintree(hasher: PoseidonHasher<F, P>) -> anyhow::Result<Self> {
+        fn zkhash_layout<F: PrimeField>(_: &[F], t: usize, left: &F, right: &F) -> Vec<F> {
+            let mut input = vec![*left, *right];
+            input.resize(t, F::zero());
+            input
+        }
+
+        Self::new_for_bintree(None, false, Some(zkhash_layout::<F>), hasher)?.with_output_index(0)
+    }
+
+    /// Creates a hash function for trees whose nodes have `arity` children (2, 4, 8 or 16).
+    /// The children take the last `arity` state elements, the summary fills the rest.
+    pub fn new_for_tree(
+        arity: usize,
+        summary: Option<Vec<F>>,
+        rand: bool,
+        summary_fn: Option<SummaryFn<F>>,
+        hasher: PoseidonHasher<F, P>,
     ) -> anyhow::Result<Self> {
         let method = hasher.method;
 
+        // check if arity is valid(2, 4, 8, 16)
+        if arity != 2 && arity != 4 && arity != 8 && arity != 16 {
+            return Err(anyhow::anyhow!("Invalid arity for Poseidon hash function"));
+        }
+
+        let input_len = method.statesize();
+        if arity >= input_len {
+            return Err(anyhow::anyhow!(
+                "Arity {} does not fit the Poseidon state size {}",
+                arity,
+                input_len
+            ));
+        }
+
         if summary_fn.is_some() {
             return Ok(PoseidonHash {
                 method,
                 summary: vec![],
                 summary_fn,
+                arity,
+                mode: HashMode::Permutation,
+                output_index: 1,
                 hasher: hasher.hasher,
             });
         }
 
-        let input_len = method.statesize();
-        let pad_len = input_len - 2; // 2 for bin tree
+        let pad_len = input_len - arity;
         let mut padding = vec![];
         if let Some(summary) = summary {
             for pad in summary.iter().take(pad_len) {
@@ -87,21 +196,324 @@ impl<F: PrimeField> PoseidonHash<F> {
             method,
             summary: padding,
             summary_fn,
+            arity,
+            mode: HashMode::Permutation,
+            output_index: 1,
+            hasher: hasher.hasher,
+        })
+    }
+
+    /// Creates a 2-to-1 or 4-to-1 feed-forward compression function `Trunc(P(x) + x)`,
+    /// as recommended in the Poseidon2 paper. The children fill the state from the
+    /// front, the rest is zero, and the first `outputs` elements are kept.
+    pub fn new_for_compression(
+        arity: usize,
+        outputs: usize,
+        hasher: PoseidonHasher<F, P>,
+    ) -> anyhow::Result<Self> {
+        let method = hasher.method;
+
+        // check if arity is valid(2, 4)
+        if arity != 2 && arity != 4 {
+            return Err(anyhow::anyhow!(
+                "Invalid arity for Poseidon compression function"
+            ));
+        }
+
+        let input_len = method.statesize();
+        if arity > input_len || outputs == 0 || outputs > input_len {
+            return Err(anyhow::anyhow!(
+                "Compression of {} into {} elements does not fit the Poseidon state size {}",
+                arity,
+                outputs,
+                input_len
+            ));
+        }
+
+        Ok(PoseidonHash {
+            method,
+            summary: vec![],
+            summary_fn: None,
+            arity,
+            mode: HashMode::Compression { outputs },
+            output_index: 0,
             hasher: hasher.hasher,
         })
     }
+
+    /// Creates a compression function for Merkle trees with digests of `digest_len`
+    /// elements. Both digests fill the state from the front, so `2 * digest_len` must
+    /// not exceed the state size, e.g. 4 Goldilocks elements with a width 12 state or
+    /// 8 BabyBear elements with a width 16 state.
+    pub fn new_for_digests(
+        digest_len: usize,
+        hasher: PoseidonHasher<F, P>,
+    ) -> anyhow::Result<Self> {
+        let input_len = hasher.method.statesize();
+        if digest_len == 0 || 2 * digest_len > input_len {
+            return Err(anyhow::anyhow!(
+                "Two digests of {} elements do not fit the Poseidon state size {}",
+                digest_len,
+                input_len
+            ));
+        }
+
+        PoseidonHash::new_for_compression(2, digest_len, hasher)
+    }
+
+    /// Selects which output element becomes the node value, e.g. 0 for circuits reading
+    /// the first lane. The index alone does not reproduce `zkhash::merkle_tree::merkle_tree_fp`,
+    /// which also needs the `[left, right, 0]` layout, see `new_for_zkhash_bintree`.
+    pub fn with_output_index(mut self, output_index: usize) -> anyhow::Result<Self> {
+        let outputs = match self.mode {
+            HashMode::Permutation => self.method.statesize(),
+            HashMode::Compression { outputs } => outputs,
+        };
+        if output_index >= outputs {
+            return Err(anyhow::anyhow!(
+                "Output index {} is out of range for a hash output of {} elements",
+                output_index,
+                outputs
+            ));
+        }
+        self.output_index = output_index;
+        Ok(self)
+    }
+
+    /// Returns the configuration a verifier needs to recompute roots with this hash function.
+    pub fn config(&self) -> HashConfig<F> {
+        let padding = if self.summary_fn.is_some() {
+            PaddingMode::Custom
+        } else if self.summary.iter().all(|pad| pad.is_zero()) {
+            PaddingMode::Zero
+        } else {
+            PaddingMode::Summary(self.summary.clone())
+        };
+
+        HashConfig {
+            method: self.method,
+            permutation: self.hasher.kind(),
+            arity: self.arity,
+            padding,
+            mode: self.mode,
+            output_index: self.output_index,
+        }
+    }
+
+    // Trunc(P(x) + x), keeping the first `outputs` elements
+    fn feed_forward(&self, input: &[F], outputs: usize) -> Vec<F> {
+        let mut output = self.hasher.permutation(input);
+        for (out, x) in output.iter_mut().zip(input.iter()) {
+            *out += x;
+        }
+        output.truncate(outputs);
+        output
+    }
+
+    /// Builds the permutation input for the children of a node.
+    pub fn pad_children(&self, children: &[F]) -> Vec<F> {
+        if let HashMode::Compression { .. } = self.mode {
+            let mut padding = children.to_vec();
+            padding.resize(self.method.statesize(), F::zero());
+            padding
+        } else if let Some(summary_fn) = self.summary_fn {
+            let last = children.len() - 1;
+            summary_fn(
+                &children[1..last],
+                self.method.statesize(),
+                &children[0],
+                &children[last],
+            )
+        } else {
+            let mut padding = self.summary.clone();
+            padding.extend_from_slice(children);
+            padding
+        }
+    }
+}
+
+impl<F: PrimeField, P: Permutation<F> + Clone> PoseidonHash<F, P> {
+    /// Hashes a record of any length into a single leaf value with a duplex sponge
+    /// whose capacity holds at least 254 bits, see `capacity_for`.
+    pub fn hash_record(&self, record: &[F]) -> anyhow::Result<F> {
+        let t = self.hasher.get_t();
+        let capacity = capacity_for::<F>();
+        if capacity >= t {
+            return Err(anyhow::anyhow!(
+                "Poseidon state size {} is too small for a sponge with capacity {}",
+                t,
+                capacity
+            ));
+        }
+
+        let mut sponge = Sponge::new(self.hasher.clone(), t - capacity)?;
+        sponge.absorb(record);
+        Ok(sponge.squeeze(1)[0])
+    }
+}
+
+impl<F: PrimeField, P: Permutation<F>, const N: usize> super::DigestFunction<F, N>
+    for PoseidonHash<F, P>
+{
+    fn compress(&self, left: &[F; N], right: &[F; N]) -> anyhow::Result<[F; N]> {
+        if self.mode != (HashMode::Compression { outputs: N }) || 2 * N > self.method.statesize() {
+            return Err(anyhow::anyhow!(
+                "Poseidon hash does not compress into digests of {} elements",
+                N
+            ));
+        }
+
+        let mut input = Vec::with_capacity(self.method.statesize());
+        input.extend_from_slice(left);
+        input.extend_from_slice(right);
+        input.resize(self.method.statesize(), F::zero());
+        let output = self.feed_forward(&input, N);
+        Ok(output.try_into().expect("Truncated to the digest length"))
+    }
+}
+
+// Digest trees are binary, every node compresses two digests
+impl<F: PrimeField, P: Permutation<F>, const N: usize> super::NodeHash<[F; N]>
+    for PoseidonHash<F, P>
+{
+    fn node_arity(&self) -> usize {
+        2
+    }
+
+    fn zero_node(&self) -> [F; N] {
+        super::DigestFunction::zero(self)
+    }
+
+    fn hash_nodes(&self, children: &[[F; N]]) -> anyhow::Result<[F; N]> {
+        match children {
+            [left, right] => super::DigestFunction::compress(self, left, right),
+            _ => Err(anyhow::anyhow!("Invalid number of children, expected 2")),
+        }
+    }
+}
+
+/// How the permutation input is filled besides the children of a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaddingMode<F: PrimeField> {
+    Zero,
+    Summary(Vec<F>),
+    // padding function, which cannot be recorded itself
+    Custom,
+}
+
+/// Hash configuration recorded alongside encoded proofs, so that a verifier can refuse
+/// proofs generated with a different permutation, field, width, arity or padding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashConfig<F: PrimeField> {
+    pub method: PoseidonMethod, // field and state width
+    pub permutation: PermutationKind,
+    pub arity: usize,
+    pub padding: PaddingMode<F>,
+    pub mode: HashMode,
+    pub output_index: usize,
 }
 
 // Poseidon hash function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PoseidonMethod {
     Bn256,
     Goldilocks(usize),
     Vesta,
+    Bls12(usize),
+    Pallas(usize),
+    BabyBear(usize),
 }
 
-pub struct PoseidonHasher<F: PrimeField> {
+pub struct PoseidonHasher<F: PrimeField, P: Permutation<F> = Poseidon2<F>> {
     pub method: PoseidonMethod,
-    pub hasher: Poseidon2<F>,
+    pub hasher: P,
+    field: PhantomData<F>,
+}
+
+impl<F: PrimeField, P: Permutation<F>> PoseidonHasher<F, P> {
+    // pair any permutation, e.g. gmimc or neptune, with the method describing its field and width
+    pub fn new(method: PoseidonMethod, hasher: P) -> anyhow::Result<Self> {
+        if hasher.get_t() != method.statesize() {
+            return Err(anyhow::anyhow!(
+                "Permutation width {} does not match statesize {}",
+                hasher.get_t(),
+                method.statesize()
+            ));
+        }
+
+        Ok(PoseidonHasher {
+            method,
+            hasher,
+            field: PhantomData,
+        })
+    }
+}
+
+// Poseidon2 and Poseidon instances of one field and width
+type Instances<F> = (
+    &'static Arc<Poseidon2Params<F>>,
+    &'static Arc<PoseidonParams<F>>,
+);
+
+fn goldilocks_instances(t: usize) -> anyhow::Result<Instances<FpGoldiLocks>> {
+    match t {
+        8 => Ok((
+            &POSEIDON2_GOLDILOCKS_8_PARAMS,
+            &POSEIDON_GOLDILOCKS_8_PARAMS,
+        )),
+        12 => Ok((
+            &POSEIDON2_GOLDILOCKS_12_PARAMS,
+            &POSEIDON_GOLDILOCKS_12_PARAMS,
+        )),
+        16 => Ok((
+            &POSEIDON2_GOLDILOCKS_16_PARAMS,
+            &POSEIDON_GOLDILOCKS_16_PARAMS,
+        )),
+        20 => Ok((
+            &POSEIDON2_GOLDILOCKS_20_PARAMS,
+            &POSEIDON_GOLDILOCKS_20_PARAMS,
+        )),
+        _ => Err(anyhow::anyhow!(
+            "Invalid statesize {} for Goldilocks Poseidon hash function",
+            t
+        )),
+    }
+}
+
+fn bls12_instances(t: usize) -> anyhow::Result<Instances<FpBLS12>> {
+    match t {
+        2 => Ok((&POSEIDON2_BLS_2_PARAMS, &POSEIDON_BLS_2_PARAMS)),
+        3 => Ok((&POSEIDON2_BLS_3_PARAMS, &POSEIDON_BLS_3_PARAMS)),
+        4 => Ok((&POSEIDON2_BLS_4_PARAMS, &POSEIDON_BLS_4_PARAMS)),
+        8 => Ok((&POSEIDON2_BLS_8_PARAMS, &POSEIDON_BLS_8_PARAMS)),
+        _ => Err(anyhow::anyhow!(
+            "Invalid statesize {} for BLS12 Poseidon hash function",
+            t
+        )),
+    }
+}
+
+fn pallas_instances(t: usize) -> anyhow::Result<Instances<FpPallas>> {
+    match t {
+        3 => Ok((&POSEIDON2_PALLAS_3_PARAMS, &POSEIDON_PALLAS_3_PARAMS)),
+        4 => Ok((&POSEIDON2_PALLAS_4_PARAMS, &POSEIDON_PALLAS_4_PARAMS)),
+        8 => Ok((&POSEIDON2_PALLAS_8_PARAMS, &POSEIDON_PALLAS_8_PARAMS)),
+        _ => Err(anyhow::anyhow!(
+            "Invalid statesize {} for Pallas Poseidon hash function",
+            t
+        )),
+    }
+}
+
+fn babybear_instances(t: usize) -> anyhow::Result<Instances<FpBabyBear>> {
+    match t {
+        16 => Ok((&POSEIDON2_BABYBEAR_16_PARAMS, &POSEIDON_BABYBEAR_16_PARAMS)),
+        24 => Ok((&POSEIDON2_BABYBEAR_24_PARAMS, &POSEIDON_BABYBEAR_24_PARAMS)),
+        _ => Err(anyhow::anyhow!(
+            "Invalid statesize {} for BabyBear Poseidon hash function",
+            t
+        )),
+    }
 }
 
 impl PoseidonMethod {
@@ -110,32 +522,16 @@ impl PoseidonMethod {
         Ok(PoseidonHasher {
             method: PoseidonMethod::Bn256,
             hasher: poseidon2,
+            field: PhantomData,
         })
     }
 
     pub fn new_goldilocks(rounds: usize) -> anyhow::Result<PoseidonHasher<FpGoldiLocks>> {
-        // check if rounds is valid(8, 12, 16, 20)
-        if rounds != 8 && rounds != 12 && rounds != 16 && rounds != 20 {
-            return Err(anyhow::anyhow!(
-                "Invalid rounds number for Goldilocks Poseidon hash function"
-            ));
-        }
-
-        let poseidon2 = match rounds {
-            8 => Poseidon2::new(&POSEIDON2_GOLDILOCKS_8_PARAMS),
-            12 => Poseidon2::new(&POSEIDON2_GOLDILOCKS_12_PARAMS),
-            16 => Poseidon2::new(&POSEIDON2_GOLDILOCKS_16_PARAMS),
-            20 => Poseidon2::new(&POSEIDON2_GOLDILOCKS_20_PARAMS),
-            _ => {
-                return Err(anyhow::anyhow!(
-                    "Invalid rounds number for Goldilocks Poseidon hash function"
-                ))
-            }
-        };
-
+        let (params, _) = goldilocks_instances(rounds)?;
         Ok(PoseidonHasher {
             method: PoseidonMethod::Goldilocks(rounds),
-            hasher: poseidon2,
+            hasher: Poseidon2::new(params),
+            field: PhantomData,
         })
     }
 
@@ -144,6 +540,97 @@ impl PoseidonMethod {
         Ok(PoseidonHasher {
             method: PoseidonMethod::Vesta,
             hasher: poseidon2,
+            field: PhantomData,
+        })
+    }
+
+    pub fn new_bls12(t: usize) -> anyhow::Result<PoseidonHasher<FpBLS12>> {
+        let (params, _) = bls12_instances(t)?;
+        Ok(PoseidonHasher {
+            method: PoseidonMethod::Bls12(t),
+            hasher: Poseidon2::new(params),
+            field: PhantomData,
+        })
+    }
+
+    pub fn new_pallas(t: usize) -> anyhow::Result<PoseidonHasher<FpPallas>> {
+        let (params, _) = pallas_instances(t)?;
+        Ok(PoseidonHasher {
+            method: PoseidonMethod::Pallas(t),
+            hasher: Poseidon2::new(params),
+            field: PhantomData,
+        })
+    }
+
+    pub fn new_babybear(t: usize) -> anyhow::Result<PoseidonHasher<FpBabyBear>> {
+        let (params, _) = babybear_instances(t)?;
+        Ok(PoseidonHasher {
+            method: PoseidonMethod::BabyBear(t),
+            hasher: Poseidon2::new(params),
+            field: PhantomData,
+        })
+    }
+
+    // classic poseidon, e.g. for circomlib-style circuits
+    pub fn new_poseidon_bn256() -> anyhow::Result<PoseidonHasher<FpBN256, Poseidon<FpBN256>>> {
+        let poseidon = Poseidon::new(&POSEIDON_BN_PARAMS);
+        Ok(PoseidonHasher {
+            method: PoseidonMethod::Bn256,
+            hasher: poseidon,
+            field: PhantomData,
+        })
+    }
+
+    pub fn new_poseidon_goldilocks(
+        t: usize,
+    ) -> anyhow::Result<PoseidonHasher<FpGoldiLocks, Poseidon<FpGoldiLocks>>> {
+        let (_, params) = goldilocks_instances(t)?;
+        Ok(PoseidonHasher {
+            method: PoseidonMethod::Goldilocks(t),
+            hasher: Poseidon::new(params),
+            field: PhantomData,
+        })
+    }
+
+    pub fn new_poseidon_vesta() -> anyhow::Result<PoseidonHasher<FpVesta, Poseidon<FpVesta>>> {
+        let poseidon = Poseidon::new(&POSEIDON_VESTA_PARAMS);
+        Ok(PoseidonHasher {
+            method: PoseidonMethod::Vesta,
+            hasher: poseidon,
+            field: PhantomData,
+        })
+    }
+
+    pub fn new_poseidon_bls12(
+        t: usize,
+    ) -> anyhow::Result<PoseidonHasher<FpBLS12, Poseidon<FpBLS12>>> {
+        let (_, params) = bls12_instances(t)?;
+        Ok(PoseidonHasher {
+            method: PoseidonMethod::Bls12(t),
+            hasher: Poseidon::new(params),
+            field: PhantomData,
+        })
+    }
+
+    pub fn new_poseidon_pallas(
+        t: usize,
+    ) -> anyhow::Result<PoseidonHasher<FpPallas, Poseidon<FpPallas>>> {
+        let (_, params) = pallas_instances(t)?;
+        Ok(PoseidonHasher {
+            method: PoseidonMethod::Pallas(t),
+            hasher: Poseidon::new(params),
+            field: PhantomData,
+        })
+    }
+
+    pub fn new_poseidon_babybear(
+        t: usize,
+    ) -> anyhow::Result<PoseidonHasher<FpBabyBear, Poseidon<FpBabyBear>>> {
+        let (_, params) = babybear_instances(t)?;
+        Ok(PoseidonHasher {
+            method: PoseidonMethod::BabyBear(t),
+            hasher: Poseidon::new(params),
+            field: PhantomData,
         })
     }
 
@@ -153,9 +640,58 @@ impl PoseidonMethod {
             PoseidonMethod::Bn256 => 3,
             PoseidonMethod::Goldilocks(len) => len.to_owned(),
             PoseidonMethod::Vesta => 3,
+            PoseidonMethod::Bls12(len) => len.to_owned(),
+            PoseidonMethod::Pallas(len) => len.to_owned(),
+            PoseidonMethod::BabyBear(len) => len.to_owned(),
+        }
+    }
+
+    // get statesizes of the shipped instances
+    pub fn supported_statesizes(&self) -> &'static [usize] {
+        match self {
+            PoseidonMethod::Bn256 => &[3],
+            PoseidonMethod::Goldilocks(_) => &[8, 12, 16, 20],
+            PoseidonMethod::Vesta => &[3],
+            PoseidonMethod::Bls12(_) => &[2, 3, 4, 8],
+            PoseidonMethod::Pallas(_) => &[3, 4, 8],
+            PoseidonMethod::BabyBear(_) => &[16, 24],
         }
     }
 
+    // get field identifier, used in encoded proofs
+    pub fn field_id(&self) -> u8 {
+        match self {
+            PoseidonMethod::Bn256 => 0,
+            PoseidonMethod::Goldilocks(_) => 1,
+            PoseidonMethod::Vesta => 2,
+            PoseidonMethod::Bls12(_) => 3,
+            PoseidonMethod::Pallas(_) => 4,
+            PoseidonMethod::BabyBear(_) => 5,
+        }
+    }
+
+    // rebuild method from field identifier and statesize
+    pub fn from_field_id(id: u8, statesize: usize) -> anyhow::Result<PoseidonMethod> {
+        let method = match id {
+            0 => PoseidonMethod::Bn256,
+            1 => PoseidonMethod::Goldilocks(statesize),
+            2 => PoseidonMethod::Vesta,
+            3 => PoseidonMethod::Bls12(statesize),
+            4 => PoseidonMethod::Pallas(statesize),
+            5 => PoseidonMethod::BabyBear(statesize),
+            _ => return Err(anyhow::anyhow!("Unknown field identifier {}", id)),
+        };
+        let valid = method.supported_statesizes().contains(&statesize);
+        if !valid {
+            return Err(anyhow::anyhow!(
+                "Invalid statesize {} for Poseidon hash function",
+                statesize
+            ));
+        }
+
+        Ok(method)
+    }
+
     // gen reandom input
     pub fn input_rand_gen<F: PrimeField>(&self) -> anyhow::Result<Vec<F>> {
         let t = self.statesize();
@@ -177,18 +713,21 @@ impl PoseidonMethod {
             ));
         }
 
-        // read to F vector
-        Ok(input.iter().map(|s| from_hex(s)).collect())
+        // read to F vector, rejecting non-canonical encodings
+        input
+            .iter()
+            .map(|s| {
+                try_from_hex_strict(s)
+                    .map_err(|err| anyhow::anyhow!("Invalid field element {:?}: {}", s, err))
+            })
+            .collect()
     }
 
     // read input from cbor
-    pub fn input_read_from_cbor<F: PrimeField + DeserializeOwned>(
-        &self,
-        input: &str,
-    ) -> anyhow::Result<Vec<F>> {
+    pub fn input_read_from_cbor<F: PrimeField>(&self, input: &str) -> anyhow::Result<Vec<F>> {
         let t = self.statesize();
-        // decode cbor
-        let data: Vec<F> = decode_from_cbor_string(input);
+        // decode cbor, rejecting non-canonical encodings
+        let data: Vec<F> = try_decode_from_cbor_string_strict(input)?;
         // check length
         if data.len() != t {
             return Err(anyhow::anyhow!(
@@ -1,9 +1,29 @@
 mod test {
+    use ark_ff::{BigInteger, PrimeField};
     use merkle::{
-        hash::poseidon::{PoseidonHash, PoseidonMethod},
-        merkle::MerkleTree,
+        hash::poseidon::{HashMode, PoseidonHash, PoseidonHasher, PoseidonMethod},
+        hash::{DigestFunction, HashFunction},
+        merkle::{
+            encoding::ENCODING_VERSION,
+            hash_children,
+            incremental::IncrementalMerkleTree,
+            multiproof::MultiProof,
+            sparse::{ExclusionProof, SparseIndex, SparseMerkleTree},
+            MerkleTree, Proof,
+        },
+    };
+    use std::cell::Cell;
+    use zkhash::fields::{
+        babybear::FpBabyBear, bls12::FpBLS12, bn256::FpBN256, goldilocks::FpGoldiLocks,
+        pallas::FpPallas, vesta::FpVesta,
+    };
+    use zkhash::{
+        gmimc::{gmimc::Gmimc, gmimc_instance_bn256::GMIMC_BN_3_PARAMS},
+        neptune::{neptune::Neptune, neptune_instances::NEPTUNE_BN_PARAMS},
+        permutation::PermutationKind,
+        poseidon::{poseidon::Poseidon, poseidon_instance_bn256::POSEIDON_BN_PARAMS},
+        poseidon2::{poseidon2::Poseidon2, poseidon2_instance_bn256::POSEIDON2_BN256_PARAMS},
     };
-    use zkhash::fields::{bn256::FpBN256, goldilocks::FpGoldiLocks, vesta::FpVesta};
     #[test]
     fn test_bn256() -> anyhow::Result<()> {
         let n = 4;
@@ -105,6 +125,690 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_standalone_verify() -> anyhow::Result<()> {
+        let n = 4;
+
+        let hasher = PoseidonMethod::new_bn256()?;
+        let hash_function = PoseidonHash::new_for_bintree(None, false, None, hasher)?;
+        let mut tree = MerkleTree::new_with_levels(n, hash_function)?;
+
+        tree.insert_leaf(2, FpBN256::from(42u64))?;
+        tree.insert_leaf(5, FpBN256::from(7u64))?;
+        let root = tree.root();
+        let proof = tree.get_proof(5)?;
+
+        // verifier side: only the published root and the hash configuration
+        let hasher = PoseidonMethod::new_bn256()?;
+        let verifier_hash = PoseidonHash::new_for_bintree(None, false, None, hasher)?;
+
+        assert!(proof.verify(&root, &verifier_hash)?);
+        assert!(proof.verify_trusted_root(&verifier_hash)?);
+        assert!(!proof.verify(&FpBN256::from(1u64), &verifier_hash)?);
+
+        let mut forged = proof.clone();
+        forged.value = FpBN256::from(8u64);
+        assert!(!forged.verify(&root, &verifier_hash)?);
+
+        forged = proof.clone();
+        forged.index = 4;
+        assert!(!forged.verify(&root, &verifier_hash)?);
+
+        forged.index = 1 << proof.siblings.len();
+        assert!(!forged.verify(&root, &verifier_hash)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sparse() -> anyhow::Result<()> {
+        let depth = 256;
+
+        let hasher = PoseidonMethod::new_bn256()?;
+        let hash_function = PoseidonHash::new_for_bintree(None, false, None, hasher)?;
+        let mut tree = SparseMerkleTree::new(depth, hash_function)?;
+        let empty_root = tree.root();
+
+        let mut far = SparseIndex::from(3u64);
+        far.muln(200);
+        tree.insert_leaf(far, FpBN256::from(123u64))?;
+        tree.insert_leaf(SparseIndex::from(5u64), FpBN256::from(7u64))?;
+        assert_ne!(tree.root(), empty_root);
+        // only the two leaf-to-root paths are stored
+        assert!(tree.num_nodes() <= 2 * (depth + 1));
+
+        let proof = tree.get_proof(&far)?;
+        assert!(!proof.empty);
+        assert!(tree.prove(proof.clone())?);
+
+        let empty_proof = tree.get_proof(&SparseIndex::from(4u64))?;
+        assert!(empty_proof.empty);
+        let hasher = PoseidonMethod::new_bn256()?;
+        let verifier_hash = PoseidonHash::new_for_bintree(None, false, None, hasher)?;
+        assert!(empty_proof.verify(&tree.root(), &verifier_hash)?);
+
+        let mut forged = proof.clone();
+        forged.value = FpBN256::from(124u64);
+        assert!(!tree.prove(forged)?);
+
+        tree.insert_leaf(far, FpBN256::from(0u64))?;
+        tree.insert_leaf(SparseIndex::from(5u64), FpBN256::from(0u64))?;
+        assert_eq!(tree.root(), empty_root);
+        assert_eq!(tree.num_nodes(), 0);
+        assert!(!tree.prove(proof)?);
+
+        let mut tree = SparseMerkleTree::new(4, verifier_hash)?;
+        assert!(tree
+            .insert_leaf(SparseIndex::from(16u64), FpBN256::from(1u64))
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sparse_exclusion() -> anyhow::Result<()> {
+        let hasher = PoseidonMethod::new_bn256()?;
+        let hash_function = PoseidonHash::new_for_bintree(None, false, None, hasher)?;
+        let mut tree = SparseMerkleTree::new(64, hash_function)?;
+
+        let member = SparseIndex::from(0xdead_beefu64);
+        let absent = SparseIndex::from(0xdead_beeeu64);
+        tree.insert_leaf(member, FpBN256::from(1u64))?;
+
+        let exclusion = tree.get_exclusion_proof(&absent)?;
+        assert!(tree.prove_exclusion(exclusion.clone())?);
+        assert!(tree.get_exclusion_proof(&member).is_err());
+
+        let hasher = PoseidonMethod::new_bn256()?;
+        let verifier_hash = PoseidonHash::new_for_bintree(None, false, None, hasher)?;
+        let root = tree.root();
+        assert!(exclusion.verify(&root, &verifier_hash)?);
+        assert!(tree
+            .get_proof(&absent)?
+            .verify_exclusion(&root, &verifier_hash)?);
+
+        // a membership proof relabelled as empty must be rejected
+        let mut crafted = tree.get_proof(&member)?;
+        assert!(!crafted.verify_exclusion(&root, &verifier_hash)?);
+        crafted.empty = true;
+        assert!(!crafted.verify(&root, &verifier_hash)?);
+        assert!(!crafted.verify_exclusion(&root, &verifier_hash)?);
+        assert!(crafted.clone().into_exclusion(&verifier_hash).is_err());
+
+        // reusing the siblings of a populated leaf does not prove it empty
+        let forged = ExclusionProof {
+            index: member,
+            siblings: crafted.siblings,
+            root,
+        };
+        assert!(!forged.verify(&root, &verifier_hash)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_proof() -> anyhow::Result<()> {
+        let hasher = PoseidonMethod::new_goldilocks(8)?;
+        let hash_function = PoseidonHash::new_for_bintree(None, false, None, hasher)?;
+        let leaves = (0..64u64).map(FpGoldiLocks::from).collect();
+        let tree = MerkleTree::new(leaves, hash_function)?;
+
+        let indices = [3, 2, 17, 40, 41, 63, 17];
+        let proof = tree.get_multi_proof(&indices)?;
+        assert_eq!(proof.indices, vec![2, 3, 17, 40, 41, 63]);
+        assert!(tree.prove_multi(proof.clone())?);
+
+        // far fewer nodes than six single proofs of six siblings each
+        assert!(proof.siblings.len() < 6 * tree.height);
+
+        let hasher = PoseidonMethod::new_goldilocks(8)?;
+        let verifier_hash = PoseidonHash::new_for_bintree(None, false, None, hasher)?;
+        assert!(proof.verify(&tree.root(), &verifier_hash)?);
+
+        let mut forged = proof.clone();
+        forged.values[2] = FpGoldiLocks::from(1u64);
+        assert!(!forged.verify(&tree.root(), &verifier_hash)?);
+
+        forged = proof.clone();
+        forged.siblings.push(FpGoldiLocks::from(0u64));
+        assert!(!forged.verify(&tree.root(), &verifier_hash)?);
+
+        forged = proof.clone();
+        forged.siblings.pop();
+        assert!(!forged.verify(&tree.root(), &verifier_hash)?);
+
+        forged = proof.clone();
+        forged.indices.swap(0, 1);
+        assert!(!forged.verify(&tree.root(), &verifier_hash)?);
+
+        let single = tree.get_multi_proof(&[5])?;
+        assert_eq!(single.siblings, tree.get_proof(5)?.siblings);
+        assert!(tree.get_multi_proof(&[64]).is_err());
+
+        Ok(())
+    }
+
+    // Counts the invocations of the wrapped hash function
+    #[cfg(test)]
+    struct CountingHash<H> {
+        inner: H,
+        calls: Cell<usize>,
+    }
+
+    #[cfg(test)]
+    impl<F: PrimeField, H: HashFunction<F>> HashFunction<F> for CountingHash<H> {
+        fn hash(&self, left: &F, right: &F) -> anyhow::Result<Vec<F>> {
+            self.calls.set(self.calls.get() + 1);
+            self.inner.hash(left, right)
+        }
+
+        fn zero(&self) -> F {
+            self.inner.zero()
+        }
+
+        fn pad(&self, left: &F, right: &F) -> Vec<F> {
+            self.inner.pad(left, right)
+        }
+    }
+
+    #[test]
+    fn test_update_leaves() -> anyhow::Result<()> {
+        let n = 6;
+
+        let hasher = PoseidonMethod::new_vesta()?;
+        let hash_function = PoseidonHash::new_for_bintree(None, false, None, hasher)?;
+        let mut sequential = MerkleTree::new_with_levels(n, hash_function)?;
+
+        let hasher = PoseidonMethod::new_vesta()?;
+        let hash_function = CountingHash {
+            inner: PoseidonHash::new_for_bintree(None, false, None, hasher)?,
+            calls: Cell::new(0),
+        };
+        let mut batched = MerkleTree::new_with_levels(n, hash_function)?;
+
+        let updates = [
+            (0, FpVesta::from(1u64)),
+            (1, FpVesta::from(2u64)),
+            (2, FpVesta::from(3u64)),
+            (31, FpVesta::from(4u64)),
+            (2, FpVesta::from(5u64)),
+        ];
+        for (index, value) in updates.iter() {
+            sequential.insert_leaf(*index, *value)?;
+        }
+
+        let calls_before = batched.hash_function().calls.get();
+        let root = batched.update_leaves(&updates)?;
+        // levels 1..=5 touch 3, 2, 2, 2, 1 parents respectively
+        assert_eq!(batched.hash_function().calls.get() - calls_before, 10);
+
+        assert_eq!(root, sequential.root());
+        assert_eq!(batched.data, sequential.data);
+        assert!(batched.prove(batched.get_proof(2)?)?);
+        assert!(batched.update_leaves(&[(32, FpVesta::from(1u64))]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_incremental() -> anyhow::Result<()> {
+        let depth = 4;
+
+        let hasher = PoseidonMethod::new_bn256()?;
+        let hash_function = PoseidonHash::new_for_bintree(None, false, None, hasher)?;
+        let mut tree = IncrementalMerkleTree::new(depth, hash_function)?;
+
+        let hasher = PoseidonMethod::new_bn256()?;
+        let hash_function = PoseidonHash::new_for_bintree(None, false, None, hasher)?;
+        let mut reference = MerkleTree::new_with_levels(depth + 1, hash_function)?;
+        assert_eq!(tree.root()?, reference.root());
+
+        let marked = [0, 5, 6, 15];
+        for i in 0..(1 << depth) {
+            let leaf = FpBN256::from(100 + i as u64);
+            let index = if marked.contains(&i) {
+                tree.append_marked(leaf)?
+            } else {
+                tree.append(leaf)?
+            };
+            assert_eq!(index, i);
+
+            reference.insert_leaf(i, leaf)?;
+            assert_eq!(tree.root()?, reference.root());
+
+            for m in marked.iter().filter(|m| **m <= i) {
+                let proof = tree.get_proof(*m)?;
+                assert_eq!(proof.siblings, reference.get_proof(*m)?.siblings);
+                assert!(reference.prove(proof)?);
+            }
+        }
+
+        assert!(tree.append(FpBN256::from(1u64)).is_err());
+        assert!(tree.get_proof(1).is_err());
+        assert!(tree.unmark(5));
+        assert!(tree.get_proof(5).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_root_history() -> anyhow::Result<()> {
+        let n = 4;
+
+        let hasher = PoseidonMethod::new_bn256()?;
+        let hash_function = PoseidonHash::new_for_bintree(None, false, None, hasher)?;
+        let mut tree = MerkleTree::new_with_levels(n, hash_function)?.with_root_history(3);
+
+        tree.insert_leaf(0, FpBN256::from(1u64))?;
+        let stale_proof = tree.get_proof(0)?;
+        let stale_root = tree.root();
+
+        tree.insert_leaf(1, FpBN256::from(2u64))?;
+        assert!(tree.is_known_root(&stale_root));
+        assert!(!tree.prove(stale_proof.clone())?);
+        assert!(tree.prove_with_history(stale_proof.clone())?);
+
+        // a forged proof against a known root is still rejected
+        let mut forged = stale_proof.clone();
+        forged.value = FpBN256::from(3u64);
+        assert!(!tree.prove_with_history(forged)?);
+
+        // unchanged roots are not recorded twice
+        tree.insert_leaf(1, FpBN256::from(2u64))?;
+        assert_eq!(tree.root_history().len(), 3);
+
+        // the window slides past the stale root after two more updates
+        tree.update_leaves(&[(2, FpBN256::from(3u64)), (3, FpBN256::from(4u64))])?;
+        assert!(tree.is_known_root(&stale_root));
+        tree.insert_leaf(4, FpBN256::from(5u64))?;
+        assert!(!tree.is_known_root(&stale_root));
+        assert!(!tree.prove_with_history(stale_proof)?);
+        assert_eq!(tree.root_history().back(), Some(&tree.root()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_arity() -> anyhow::Result<()> {
+        // quaternary tree over 10 leaves, padded to 16
+        let hasher = PoseidonMethod::new_goldilocks(8)?;
+        let hash_function = PoseidonHash::new_for_tree(4, None, false, None, hasher)?;
+        let leaves: Vec<FpGoldiLocks> = (0..10).map(|i| FpGoldiLocks::from(i as u64)).collect();
+        let mut tree = MerkleTree::new(leaves.clone(), hash_function)?;
+        assert_eq!(tree.height, 2);
+        assert_eq!(tree.data.len(), 16 + 4 + 1);
+
+        let mut level = leaves.clone();
+        level.resize(16, FpGoldiLocks::from(0u64));
+        while level.len() > 1 {
+            level = level
+                .chunks(4)
+                .map(|children| hash_children(tree.hash_function(), children))
+                .collect::<anyhow::Result<_>>()?;
+        }
+        assert_eq!(level[0], tree.root());
+
+        let proof = tree.get_proof(6)?;
+        assert_eq!(proof.siblings.len(), 2 * 3);
+        assert!(tree.prove(proof.clone())?);
+        let mut forged = proof.clone();
+        forged.index = 5;
+        assert!(!tree.prove(forged)?);
+        forged = proof.clone();
+        forged.siblings.pop();
+        assert!(!tree.prove(forged)?);
+
+        let multi_proof = tree.get_multi_proof(&[1, 2, 9, 15])?;
+        assert!(tree.prove_multi(multi_proof)?);
+
+        tree.update_leaves(&[
+            (3, FpGoldiLocks::from(7u64)),
+            (12, FpGoldiLocks::from(8u64)),
+        ])?;
+        assert!(!tree.prove(proof)?);
+        assert!(tree.prove(tree.get_proof(12)?)?);
+
+        // octal tree of 3 levels
+        let hasher = PoseidonMethod::new_goldilocks(12)?;
+        let hash_function = PoseidonHash::new_for_tree(8, None, false, None, hasher)?;
+        let mut tree = MerkleTree::new_with_levels(3, hash_function)?;
+        tree.insert_leaf(42, FpGoldiLocks::from(1u64))?;
+        let proof = tree.get_proof(42)?;
+        assert_eq!(proof.siblings.len(), 2 * 7);
+        assert!(tree.prove(proof)?);
+
+        // binary only structures and oversized arities are rejected
+        let hasher = PoseidonMethod::new_goldilocks(8)?;
+        let hash_function = PoseidonHash::new_for_tree(4, None, false, None, hasher)?;
+        assert!(SparseMerkleTree::new(4, hash_function).is_err());
+        let hasher = PoseidonMethod::new_goldilocks(8)?;
+        assert!(PoseidonHash::new_for_tree(8, None, false, None, hasher).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_proof_encoding() -> anyhow::Result<()> {
+        let hasher = PoseidonMethod::new_goldilocks(12)?;
+        let hash_function = PoseidonHash::new_for_tree(4, None, true, None, hasher)?;
+        let config = hash_function.config();
+        let leaves: Vec<FpGoldiLocks> = (1..20).map(|i| FpGoldiLocks::from(i as u64)).collect();
+        let tree = MerkleTree::new(leaves, hash_function)?;
+
+        let proof = tree.get_proof(5)?;
+        let bytes = proof.to_bytes(&config);
+        let decoded = Proof::from_bytes(&bytes, &config)?;
+        assert_eq!(decoded.to_bytes(&config), bytes);
+        assert!(decoded.verify(&tree.root(), tree.hash_function())?);
+
+        let decoded = Proof::<FpGoldiLocks>::from_cbor_string(&proof.to_cbor_string()?)?;
+        assert_eq!(decoded.to_bytes(&config), bytes);
+
+        let multi_proof = tree.get_multi_proof(&[0, 5, 17])?;
+        let decoded = MultiProof::from_bytes(&multi_proof.to_bytes(&config), &config)?;
+        assert!(tree.prove_multi(decoded)?);
+        let decoded = MultiProof::<FpGoldiLocks>::from_cbor_string(&multi_proof.to_cbor_string()?)?;
+        assert!(tree.prove_multi(decoded)?);
+
+        // a verifier with another padding, width or arity refuses the proof
+        let hasher = PoseidonMethod::new_goldilocks(12)?;
+        let other = PoseidonHash::new_for_tree(4, None, false, None, hasher)?.config();
+        assert!(Proof::from_bytes(&bytes, &other).is_err());
+        let hasher = PoseidonMethod::new_goldilocks(16)?;
+        let other = PoseidonHash::new_for_tree(4, None, true, None, hasher)?;
+        assert!(Proof::from_bytes(&bytes, &other.config()).is_err());
+        let mut other = config.clone();
+        other.arity = 8;
+        assert!(Proof::from_bytes(&bytes, &other).is_err());
+
+        // unknown versions, truncated input and unreduced elements are rejected
+        let mut corrupted = bytes.clone();
+        corrupted[0] = ENCODING_VERSION + 1;
+        assert!(Proof::from_bytes(&corrupted, &config).is_err());
+        assert!(Proof::from_bytes(&bytes[..bytes.len() - 1], &config).is_err());
+        let mut corrupted = bytes.clone();
+        let root_offset = bytes.len() - 4 - 9 * 8 - 8;
+        corrupted[root_offset..root_offset + 8].copy_from_slice(&[0xff; 8]);
+        assert!(Proof::from_bytes(&corrupted, &config).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_strict_input() -> anyhow::Result<()> {
+        let method = PoseidonMethod::new_goldilocks(8)?.method;
+        let input: Vec<FpGoldiLocks> = method.input_rand_gen()?;
+
+        let hex = method.input_parse_to_hex_string_array(&input)?;
+        let strings: Vec<&str> = hex.iter().map(|s| s.as_str()).collect();
+        assert_eq!(
+            method.input_read_from_hex_string_array::<FpGoldiLocks>(&strings)?,
+            input
+        );
+        let cbor = method.input_parse_to_cbor_string(&input);
+        assert_eq!(method.input_read_from_cbor::<FpGoldiLocks>(&cbor)?, input);
+
+        // the modulus reduces to zero, a shorter encoding would alias the same value
+        let mut strings = strings.clone();
+        strings[0] = "0xffffffff00000001";
+        assert!(method
+            .input_read_from_hex_string_array::<FpGoldiLocks>(&strings)
+            .is_err());
+        strings[0] = "0x00";
+        assert!(method
+            .input_read_from_hex_string_array::<FpGoldiLocks>(&strings)
+            .is_err());
+        assert!(method.input_read_from_cbor::<FpGoldiLocks>("AAAA").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_more_fields() -> anyhow::Result<()> {
+        let hasher = PoseidonMethod::new_pallas(3)?;
+        let hash_function = PoseidonHash::new_for_bintree(None, false, None, hasher)?;
+        let mut tree = MerkleTree::new_with_levels(4, hash_function)?;
+        tree.insert_leaf(3, FpPallas::from(7u64))?;
+        assert!(tree.prove(tree.get_proof(3)?)?);
+
+        let hasher = PoseidonMethod::new_bls12(4)?;
+        let hash_function = PoseidonHash::new_for_bintree(None, false, None, hasher)?;
+        let mut tree = MerkleTree::new_with_levels(4, hash_function)?;
+        tree.insert_leaf(5, FpBLS12::from(7u64))?;
+        assert!(tree.prove(tree.get_proof(5)?)?);
+
+        let hasher = PoseidonMethod::new_babybear(16)?;
+        let hash_function = PoseidonHash::new_for_tree(8, None, true, None, hasher)?;
+        let config = hash_function.config();
+        let leaves: Vec<FpBabyBear> = (0..100).map(|i| FpBabyBear::from(i as u64)).collect();
+        let tree = MerkleTree::new(leaves, hash_function)?;
+        let proof = tree.get_proof(77)?;
+        let decoded = Proof::from_bytes(&proof.to_bytes(&config), &config)?;
+        assert!(tree.prove(decoded)?);
+
+        assert!(PoseidonMethod::new_bls12(5).is_err());
+        assert!(PoseidonMethod::new_pallas(2).is_err());
+        assert!(PoseidonMethod::new_babybear(8).is_err());
+        // the width 2 instance leaves no room for a binary node next to the padding
+        let hasher = PoseidonMethod::new_bls12(2)?;
+        assert!(PoseidonHash::new_for_bintree(None, false, None, hasher).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_permutation_backends() -> anyhow::Result<()> {
+        let leaves: Vec<FpBN256> = (0..4).map(|i| FpBN256::from(i as u64)).collect();
+
+        // classic poseidon, hashing [0, left, right]
+        let hasher = PoseidonMethod::new_poseidon_bn256()?;
+        let hash_function = PoseidonHash::new_for_bintree(None, false, None, hasher)?;
+        let tree = MerkleTree::new(leaves.clone(), hash_function)?;
+        let poseidon = Poseidon::new(&POSEIDON_BN_PARAMS);
+        let node = |l: FpBN256, r: FpBN256| poseidon.permutation(&[FpBN256::from(0u64), l, r])[1];
+        let expected = node(node(leaves[0], leaves[1]), node(leaves[2], leaves[3]));
+        assert_eq!(tree.root(), expected);
+        assert!(tree.prove(tree.get_proof(2)?)?);
+
+        // gmimc paired with a matching width
+        let hasher = PoseidonHasher::new(PoseidonMethod::Bn256, Gmimc::new(&GMIMC_BN_3_PARAMS))?;
+        let hash_function = PoseidonHash::new_for_bintree(None, false, None, hasher)?;
+        let gmimc_tree = MerkleTree::new(leaves, hash_function)?;
+        assert_ne!(gmimc_tree.root(), tree.root());
+        assert!(gmimc_tree.prove(gmimc_tree.get_proof(1)?)?);
+
+        // encoded proofs record the permutation, not just the field and width
+        let poseidon2 =
+            PoseidonHash::new_for_bintree(None, false, None, PoseidonMethod::new_bn256()?)?;
+        let mut config = poseidon2.config();
+        config.permutation = PermutationKind::Poseidon;
+        assert_eq!(tree.hash_function().config(), config);
+        let bytes = tree.get_proof(2)?.to_bytes(&tree.hash_function().config());
+        assert!(Proof::from_bytes(&bytes, &tree.hash_function().config()).is_ok());
+        assert!(Proof::from_bytes(&bytes, &poseidon2.config()).is_err());
+        assert!(Proof::from_bytes(&bytes, &gmimc_tree.hash_function().config()).is_err());
+
+        // neptune only exists with width 4
+        let neptune = Neptune::new(&NEPTUNE_BN_PARAMS);
+        assert!(PoseidonHasher::new(PoseidonMethod::Bn256, neptune).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_leaves() -> anyhow::Result<()> {
+        let hasher = PoseidonMethod::new_goldilocks(8)?;
+        let hash_function = PoseidonHash::new_for_bintree(None, false, None, hasher)?;
+        let records: Vec<Vec<FpGoldiLocks>> = (0..6)
+            .map(|len| (0..len).map(|i| FpGoldiLocks::from(i as u64)).collect())
+            .collect();
+        let leaves = records
+            .iter()
+            .map(|record| hash_function.hash_record(record))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        // a prefix padded with zeros does not collide with the shorter record
+        for pair in leaves.windows(2) {
+            assert_ne!(pair[0], pair[1]);
+        }
+        let mut zero_padded = records[3].clone();
+        zero_padded.push(FpGoldiLocks::from(0u64));
+        assert_ne!(hash_function.hash_record(&zero_padded)?, leaves[3]);
+
+        let tree = MerkleTree::new(leaves, hash_function)?;
+        assert!(tree.prove(tree.get_proof(4)?)?);
+
+        // BabyBear needs nine capacity elements
+        let hasher = PoseidonMethod::new_babybear(16)?;
+        let hash_function = PoseidonHash::new_for_bintree(None, false, None, hasher)?;
+        assert!(hash_function.hash_record(&[FpBabyBear::from(1u64)]).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_compression_mode() -> anyhow::Result<()> {
+        let leaves: Vec<FpBLS12> = (0..4).map(|i| FpBLS12::from(i as u64)).collect();
+
+        // 2-to-1 compression over a width 2 state: P([l, r])[0] + l
+        let hasher = PoseidonMethod::new_bls12(2)?;
+        let hash_function = PoseidonHash::new_for_compression(2, 1, hasher)?;
+        let tree = MerkleTree::new(leaves.clone(), hash_function)?;
+        let hash_function = tree.hash_function();
+        let node = |l: FpBLS12, r: FpBLS12| hash_function.hasher.permutation(&[l, r])[0] + l;
+        let expected = node(node(leaves[0], leaves[1]), node(leaves[2], leaves[3]));
+        assert_eq!(tree.root(), expected);
+        assert_eq!(hash_function.hash(&expected, &expected)?.len(), 1);
+
+        let proof = tree.get_proof(3)?;
+        assert!(tree.prove(proof.clone())?);
+        let config = hash_function.config();
+        let bytes = proof.to_bytes(&config);
+        assert!(tree.prove(Proof::from_bytes(&bytes, &config)?)?);
+        let mut other = config.clone();
+        other.mode = HashMode::Permutation;
+        assert!(Proof::from_bytes(&bytes, &other).is_err());
+
+        // 4-to-1 compression keeping two elements
+        let hasher = PoseidonMethod::new_goldilocks(8)?;
+        let hash_function = PoseidonHash::new_for_compression(4, 2, hasher)?;
+        let children: Vec<FpGoldiLocks> = (1..5).map(|i| FpGoldiLocks::from(i as u64)).collect();
+        let mut input = children.clone();
+        input.resize(8, FpGoldiLocks::from(0u64));
+        let permuted = hash_function.hasher.permutation(&input);
+        let digest = hash_function.hash_children(&children)?;
+        assert_eq!(digest, vec![permuted[0] + input[0], permuted[1] + input[1]]);
+
+        let leaves: Vec<FpGoldiLocks> = (0..16).map(|i| FpGoldiLocks::from(i as u64)).collect();
+        let tree = MerkleTree::new(leaves, hash_function)?;
+        assert!(tree.prove(tree.get_proof(9)?)?);
+
+        // arity and output count must fit the state
+        assert!(
+            PoseidonHash::new_for_compression(8, 1, PoseidonMethod::new_goldilocks(8)?).is_err()
+        );
+        assert!(PoseidonHash::new_for_compression(4, 1, PoseidonMethod::new_bls12(2)?).is_err());
+        assert!(PoseidonHash::new_for_compression(2, 0, PoseidonMethod::new_bls12(2)?).is_err());
+        assert!(PoseidonHash::new_for_compression(2, 3, PoseidonMethod::new_bls12(2)?).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_output_index() -> anyhow::Result<()> {
+        let leaves: Vec<FpBN256> = (0..8).map(|i| FpBN256::from(i as u64)).collect();
+
+        // [left, right, 0] reading the first lane, as zkhash's MerkleTreeHash does
+        let hasher = PoseidonMethod::new_bn256()?;
+        let hash_function = PoseidonHash::new_for_zkhash_bintree(hasher)?;
+        assert_eq!(hash_function.output_index, 0);
+        let tree = MerkleTree::new(leaves.clone(), hash_function)?;
+        let mut reference = zkhash::merkle_tree::merkle_tree_fp::MerkleTree::new(Poseidon2::new(
+            &POSEIDON2_BN256_PARAMS,
+        ));
+        assert_eq!(tree.root(), reference.accumulate(&leaves));
+        assert!(tree.prove(tree.get_proof(6)?)?);
+
+        // the selected lane is part of the recorded configuration
+        let config = tree.hash_function().config();
+        let bytes = tree.get_proof(6)?.to_bytes(&config);
+        let mut other = config.clone();
+        other.output_index = 1;
+        assert!(Proof::from_bytes(&bytes, &other).is_err());
+
+        let hasher = PoseidonMethod::new_bn256()?;
+        let default_tree = MerkleTree::new(
+            leaves,
+            PoseidonHash::new_for_zkhash_bintree(hasher)?.with_output_index(1)?,
+        )?;
+        assert_ne!(default_tree.root(), tree.root());
+
+        // the index must address an output element
+        let hasher = PoseidonMethod::new_bn256()?;
+        let hash_function = PoseidonHash::new_for_bintree(None, false, None, hasher)?;
+        assert!(hash_function.with_output_index(3).is_err());
+        let hasher = PoseidonMethod::new_goldilocks(8)?;
+        let hash_function = PoseidonHash::new_for_compression(2, 4, hasher)?;
+        assert!(hash_function.with_output_index(3).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_digest_tree() -> anyhow::Result<()> {
+        // four Goldilocks elements per digest, two digests per width 12 state
+        let leaves: Vec<[FpGoldiLocks; 4]> = (0..5)
+            .map(|i| [0, 1, 2, 3].map(|j| FpGoldiLocks::from(4 * i + j)))
+            .collect();
+        let hasher = PoseidonMethod::new_goldilocks(12)?;
+        let hash_function = PoseidonHash::new_for_digests(4, hasher)?;
+        let mut tree = MerkleTree::new(leaves.clone(), hash_function)?;
+        assert_eq!(tree.height, 3);
+
+        let mut input: Vec<FpGoldiLocks> = [leaves[0], leaves[1]].concat();
+        input.resize(12, FpGoldiLocks::from(0u64));
+        let permuted = tree.hash_function().hasher.permutation(&input);
+        let expected: Vec<FpGoldiLocks> = (0..4).map(|i| permuted[i] + input[i]).collect();
+        assert_eq!(tree.data[8].to_vec(), expected);
+
+        let proof = tree.get_proof(4)?;
+        assert_eq!(proof.siblings.len(), 3);
+        assert!(tree.prove(proof.clone())?);
+        let mut forged = proof.clone();
+        forged.siblings[1][3] += FpGoldiLocks::from(1u64);
+        assert!(!tree.prove(forged)?);
+
+        tree.insert_leaf(6, leaves[0])?;
+        assert!(!tree.prove(proof)?);
+        let rebuilt = MerkleTree::new(
+            [
+                leaves.clone(),
+                vec![[FpGoldiLocks::from(0u64); 4], leaves[0]],
+            ]
+            .concat(),
+            PoseidonHash::new_for_digests(4, PoseidonMethod::new_goldilocks(12)?)?,
+        )?;
+        assert_eq!(tree.root(), rebuilt.root());
+        assert!(tree.prove(tree.get_proof(6)?)?);
+
+        // eight BabyBear elements per digest fill the whole width 16 state
+        let leaves: Vec<[FpBabyBear; 8]> = (0..4)
+            .map(|i| [0, 1, 2, 3, 4, 5, 6, 7].map(|j| FpBabyBear::from(8 * i + j)))
+            .collect();
+        let hasher = PoseidonMethod::new_babybear(16)?;
+        let hash_function = PoseidonHash::new_for_digests(8, hasher)?;
+        let tree = MerkleTree::new(leaves, hash_function)?;
+        assert!(tree.prove(tree.get_proof(2)?)?);
+
+        // two digests have to fit into the state
+        assert!(PoseidonHash::new_for_digests(9, PoseidonMethod::new_babybear(16)?).is_err());
+        let hash_function = PoseidonHash::new_for_digests(4, PoseidonMethod::new_goldilocks(12)?)?;
+        let digest = [FpGoldiLocks::from(0u64); 2];
+        assert!(hash_function.compress(&digest, &digest).is_err());
+
+        Ok(())
+    }
 }
 
 // Main function for testing
@@ -0,0 +1,412 @@
+use ark_ff::PrimeField;
+use cbor4ii::serde::{from_slice, to_vec};
+use data_encoding::BASE64;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use zkhash::{
+    fields::utils::{
+        field_byte_len, to_be_bytes, to_hex, try_from_be_bytes_strict, try_from_hex_strict,
+    },
+    permutation::PermutationKind,
+};
+
+use super::{multiproof::MultiProof, Proof};
+use crate::hash::poseidon::{HashConfig, HashMode, PaddingMode, PoseidonMethod};
+
+/// Version of the canonical binary layout, bumped on every incompatible change.
+pub const ENCODING_VERSION: u8 = 2;
+
+const KIND_PROOF: u8 = 0;
+const KIND_MULTI_PROOF: u8 = 1;
+
+const PERMUTATION_POSEIDON: u8 = 0;
+const PERMUTATION_POSEIDON2: u8 = 1;
+const PERMUTATION_GMIMC: u8 = 2;
+const PERMUTATION_NEPTUNE: u8 = 3;
+
+const PADDING_ZERO: u8 = 0;
+const PADDING_SUMMARY: u8 = 1;
+const PADDING_CUSTOM: u8 = 2;
+
+const MODE_PERMUTATION: u8 = 0;
+const MODE_COMPRESSION: u8 = 1;
+
+// Human readable form, field elements as `0x` prefixed hex strings
+#[derive(Serialize, Deserialize)]
+struct ProofRepr {
+    index: usize,
+    value: String,
+    siblings: Vec<String>,
+    root: String,
+    empty: bool,
+    arity: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MultiProofRepr {
+    indices: Vec<usize>,
+    values: Vec<String>,
+    siblings: Vec<String>,
+    height: usize,
+    arity: usize,
+    root: String,
+}
+
+impl<F: PrimeField> Serialize for Proof<F> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ProofRepr {
+            index: self.index,
+            value: hex_element(&self.value),
+            siblings: to_hex(&self.siblings),
+            root: hex_element(&self.root),
+            empty: self.empty,
+            arity: self.arity,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, F: PrimeField> Deserialize<'de> for Proof<F> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = ProofRepr::deserialize(deserializer)?;
+        Ok(Proof {
+            index: repr.index,
+            value: parse_hex_element(&repr.value).map_err(de::Error::custom)?,
+            siblings: parse_hex_elements(&repr.siblings).map_err(de::Error::custom)?,
+            root: parse_hex_element(&repr.root).map_err(de::Error::custom)?,
+            empty: repr.empty,
+            arity: repr.arity,
+        })
+    }
+}
+
+impl<F: PrimeField> Serialize for MultiProof<F> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        MultiProofRepr {
+            indices: self.indices.clone(),
+            values: to_hex(&self.values),
+            siblings: to_hex(&self.siblings),
+            height: self.height,
+            arity: self.arity,
+            root: hex_element(&self.root),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, F: PrimeField> Deserialize<'de> for MultiProof<F> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = MultiProofRepr::deserialize(deserializer)?;
+        Ok(MultiProof {
+            indices: repr.indices,
+            values: parse_hex_elements(&repr.values).map_err(de::Error::custom)?,
+            siblings: parse_hex_elements(&repr.siblings).map_err(de::Error::custom)?,
+            height: repr.height,
+            arity: repr.arity,
+            root: parse_hex_element(&repr.root).map_err(de::Error::custom)?,
+        })
+    }
+}
+
+impl<F: PrimeField> Proof<F> {
+    /// Encodes the proof in the canonical binary layout, prefixed with the hash configuration.
+    pub fn to_bytes(&self, config: &HashConfig<F>) -> Vec<u8> {
+        let mut writer = Writer::new(KIND_PROOF, config);
+        writer.u64(self.index as u64);
+        writer.u8(self.empty as u8);
+        writer.element(&self.value);
+        writer.element(&self.root);
+        writer.elements(&self.siblings);
+        writer.bytes
+    }
+
+    /// Decodes a proof from the canonical binary layout.
+    /// Proofs recorded with another hash configuration than `config` are refused.
+    pub fn from_bytes(bytes: &[u8], config: &HashConfig<F>) -> anyhow::Result<Self> {
+        let mut reader = Reader::new(bytes, KIND_PROOF, config)?;
+        let index = reader.index()?;
+        let empty = match reader.u8()? {
+            0 => false,
+            1 => true,
+            _ => return Err(anyhow::anyhow!("Invalid empty flag in encoded proof")),
+        };
+        let value = reader.element()?;
+        let root = reader.element()?;
+        let siblings = reader.elements()?;
+        reader.finish()?;
+
+        Ok(Proof {
+            index,
+            value,
+            siblings,
+            root,
+            empty,
+            arity: config.arity,
+        })
+    }
+
+    /// Encodes the proof as base64 encoded CBOR of its serde representation.
+    pub fn to_cbor_string(&self) -> anyhow::Result<String> {
+        to_cbor_string(self)
+    }
+
+    /// Decodes a proof from base64 encoded CBOR.
+    pub fn from_cbor_string(encoded_str: &str) -> anyhow::Result<Self> {
+        from_cbor_string(encoded_str)
+    }
+}
+
+impl<F: PrimeField> MultiProof<F> {
+    /// Encodes the multi-proof in the canonical binary layout, prefixed with the hash configuration.
+    pub fn to_bytes(&self, config: &HashConfig<F>) -> Vec<u8> {
+        let mut writer = Writer::new(KIND_MULTI_PROOF, config);
+        writer.u32(self.height as u32);
+        writer.u32(self.indices.len() as u32);
+        for index in self.indices.iter() {
+            writer.u64(*index as u64);
+        }
+        writer.elements(&self.values);
+        writer.elements(&self.siblings);
+        writer.element(&self.root);
+        writer.bytes
+    }
+
+    /// Decodes a multi-proof from the canonical binary layout.
+    /// Proofs recorded with another hash configuration than `config` are refused.
+    pub fn from_bytes(bytes: &[u8], config: &HashConfig<F>) -> anyhow::Result<Self> {
+        let mut reader = Reader::new(bytes, KIND_MULTI_PROOF, config)?;
+        let height = reader.u32()? as usize;
+        let num_indices = reader.length(8)?;
+        let indices = (0..num_indices)
+            .map(|_| reader.index())
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let values = reader.elements()?;
+        let siblings = reader.elements()?;
+        let root = reader.element()?;
+        reader.finish()?;
+
+        Ok(MultiProof {
+            indices,
+            values,
+            siblings,
+            height,
+            arity: config.arity,
+            root,
+        })
+    }
+
+    /// Encodes the multi-proof as base64 encoded CBOR of its serde representation.
+    pub fn to_cbor_string(&self) -> anyhow::Result<String> {
+        to_cbor_string(self)
+    }
+
+    /// Decodes a multi-proof from base64 encoded CBOR.
+    pub fn from_cbor_string(encoded_str: &str) -> anyhow::Result<Self> {
+        from_cbor_string(encoded_str)
+    }
+}
+
+fn to_cbor_string<T: Serialize>(value: &T) -> anyhow::Result<String> {
+    let buf = to_vec(Vec::new(), value)
+        .map_err(|err| anyhow::anyhow!("CBOR encoding failed: {:?}", err))?;
+    Ok(BASE64.encode(&buf))
+}
+
+fn from_cbor_string<T: de::DeserializeOwned>(encoded_str: &str) -> anyhow::Result<T> {
+    let cbor_bytes = BASE64
+        .decode(encoded_str.as_bytes())
+        .map_err(|err| anyhow::anyhow!("Failed to decode Base64: {}", err))?;
+    from_slice(&cbor_bytes).map_err(|err| anyhow::anyhow!("Failed to decode CBOR: {:?}", err))
+}
+
+fn hex_element<F: PrimeField>(element: &F) -> String {
+    to_hex(std::slice::from_ref(element)).remove(0)
+}
+
+fn parse_hex_element<F: PrimeField>(s: &str) -> anyhow::Result<F> {
+    try_from_hex_strict(s).map_err(|err| anyhow::anyhow!("{}: {:?}", err, s))
+}
+
+fn parse_hex_elements<F: PrimeField>(strings: &[String]) -> anyhow::Result<Vec<F>> {
+    strings.iter().map(|s| parse_hex_element(s)).collect()
+}
+
+// Big-endian writer for the canonical binary layout
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn new<F: PrimeField>(kind: u8, config: &HashConfig<F>) -> Writer {
+        let mut writer = Writer { bytes: vec![] };
+        writer.u8(ENCODING_VERSION);
+        writer.u8(kind);
+        writer.u8(config.method.field_id());
+        writer.u32(config.method.statesize() as u32);
+        writer.u8(match config.permutation {
+            PermutationKind::Poseidon => PERMUTATION_POSEIDON,
+            PermutationKind::Poseidon2 => PERMUTATION_POSEIDON2,
+            PermutationKind::Gmimc => PERMUTATION_GMIMC,
+            PermutationKind::Neptune => PERMUTATION_NEPTUNE,
+        });
+        writer.u32(config.arity as u32);
+        match &config.padding {
+            PaddingMode::Zero => writer.u8(PADDING_ZERO),
+            PaddingMode::Summary(summary) => {
+                writer.u8(PADDING_SUMMARY);
+                writer.elements(summary);
+            }
+            PaddingMode::Custom => writer.u8(PADDING_CUSTOM),
+        }
+        match config.mode {
+            HashMode::Permutation => writer.u8(MODE_PERMUTATION),
+            HashMode::Compression { outputs } => {
+                writer.u8(MODE_COMPRESSION);
+                writer.u32(outputs as u32);
+            }
+        }
+        writer.u32(config.output_index as u32);
+        writer
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn element<F: PrimeField>(&mut self, element: &F) {
+        self.bytes.extend_from_slice(&to_be_bytes(element));
+    }
+
+    fn elements<F: PrimeField>(&mut self, elements: &[F]) {
+        self.u32(elements.len() as u32);
+        for element in elements.iter() {
+            self.element(element);
+        }
+    }
+}
+
+// Reader for the canonical binary layout, checking the header against the expected configuration
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new<F: PrimeField>(
+        bytes: &'a [u8],
+        kind: u8,
+        config: &HashConfig<F>,
+    ) -> anyhow::Result<Reader<'a>> {
+        let mut reader = Reader { bytes };
+        let version = reader.u8()?;
+        if version != ENCODING_VERSION {
+            return Err(anyhow::anyhow!("Unsupported encoding version {}", version));
+        }
+        if reader.u8()? != kind {
+            return Err(anyhow::anyhow!("Unexpected kind of encoded proof"));
+        }
+
+        let field_id = reader.u8()?;
+        let statesize = reader.u32()? as usize;
+        let method = PoseidonMethod::from_field_id(field_id, statesize)?;
+        let permutation = match reader.u8()? {
+            PERMUTATION_POSEIDON => PermutationKind::Poseidon,
+            PERMUTATION_POSEIDON2 => PermutationKind::Poseidon2,
+            PERMUTATION_GMIMC => PermutationKind::Gmimc,
+            PERMUTATION_NEPTUNE => PermutationKind::Neptune,
+            _ => return Err(anyhow::anyhow!("Unknown permutation in encoded proof")),
+        };
+        let arity = reader.u32()? as usize;
+        let padding = match reader.u8()? {
+            PADDING_ZERO => PaddingMode::Zero,
+            PADDING_SUMMARY => PaddingMode::Summary(reader.elements()?),
+            PADDING_CUSTOM => PaddingMode::Custom,
+            _ => return Err(anyhow::anyhow!("Unknown padding mode in encoded proof")),
+        };
+        let mode = match reader.u8()? {
+            MODE_PERMUTATION => HashMode::Permutation,
+            MODE_COMPRESSION => HashMode::Compression {
+                outputs: reader.u32()? as usize,
+            },
+            _ => return Err(anyhow::anyhow!("Unknown hash mode in encoded proof")),
+        };
+        let output_index = reader.u32()? as usize;
+
+        let recorded = HashConfig {
+            method,
+            permutation,
+            arity,
+            padding,
+            mode,
+            output_index,
+        };
+        if recorded != *config {
+            return Err(anyhow::anyhow!(
+                "Proof was generated for a different hash configuration: {:?}",
+                recorded
+            ));
+        }
+
+        Ok(reader)
+    }
+
+    fn take(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(anyhow::anyhow!("Encoded proof is truncated"));
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> anyhow::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> anyhow::Result<u32> {
+        let mut buf = [0u8; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_be_bytes(buf))
+    }
+
+    fn u64(&mut self) -> anyhow::Result<u64> {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    fn index(&mut self) -> anyhow::Result<usize> {
+        usize::try_from(self.u64()?).map_err(|_| anyhow::anyhow!("Index out of bounds"))
+    }
+
+    /// Reads a count of items of `item_size` bytes, refusing counts beyond the remaining input.
+    fn length(&mut self, item_size: usize) -> anyhow::Result<usize> {
+        let len = self.u32()? as usize;
+        if len * item_size > self.bytes.len() {
+            return Err(anyhow::anyhow!("Encoded proof is truncated"));
+        }
+        Ok(len)
+    }
+
+    fn element<F: PrimeField>(&mut self) -> anyhow::Result<F> {
+        Ok(try_from_be_bytes_strict(self.take(field_byte_len::<F>())?)?)
+    }
+
+    fn elements<F: PrimeField>(&mut self) -> anyhow::Result<Vec<F>> {
+        let len = self.length(field_byte_len::<F>())?;
+        (0..len).map(|_| self.element()).collect()
+    }
+
+    fn finish(&self) -> anyhow::Result<()> {
+        if !self.bytes.is_empty() {
+            return Err(anyhow::anyhow!("Trailing bytes after encoded proof"));
+        }
+        Ok(())
+    }
+}
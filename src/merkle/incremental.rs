@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use ark_ff::PrimeField;
+
+use super::{hash_node, Proof};
+use crate::hash::HashFunction;
+
+/// Maximum depth of an incremental tree, so that every leaf index fits into a `usize`.
+pub const MAX_INCREMENTAL_DEPTH: usize = usize::BITS as usize - 1;
+
+/// Authentication path of a marked leaf, completed while later leaves are appended.
+struct Witness<F: PrimeField> {
+    value: F,
+    siblings: Vec<Option<F>>, // Siblings that are already final, None while still growing
+}
+
+/// Append-only Merkle tree which only keeps the right-most frontier, i.e. one
+/// pending left node per level, instead of the whole tree.
+pub struct IncrementalMerkleTree<F: PrimeField, H: HashFunction<F>> {
+    frontier: Vec<F>, // Left node waiting for its right sibling at each level, root when full
+    next_index: usize, // Index of the next appended leaf
+    pub depth: usize, // Number of levels below the root
+    zero_hashes: Vec<F>, // Hash of an empty subtree for every level, root included
+    marked: HashMap<usize, Witness<F>>, // Witnesses of marked leaves
+    hash_function: H, // Hash function instance
+}
+
+impl<F: PrimeField, H: HashFunction<F>> IncrementalMerkleTree<F, H> {
+    /// Creates an empty incremental Merkle tree with room for 2^depth leaves.
+    pub fn new(depth: usize, hash_function: H) -> anyhow::Result<IncrementalMerkleTree<F, H>> {
+        if depth == 0 || depth > MAX_INCREMENTAL_DEPTH {
+            return Err(anyhow::anyhow!(
+                "Invalid depth for incremental Merkle tree, expected 1..={}",
+                MAX_INCREMENTAL_DEPTH
+            ));
+        }
+        if hash_function.arity() != 2 {
+            return Err(anyhow::anyhow!(
+                "Incremental Merkle tree requires a binary hash function"
+            ));
+        }
+
+        // compute zeros
+        let mut current_zero_hash = hash_function.zero();
+        let mut zero_hashes = Vec::with_capacity(depth + 1);
+        zero_hashes.push(current_zero_hash);
+        for _ in 0..depth {
+            current_zero_hash = hash_node(&hash_function, &current_zero_hash, &current_zero_hash)?;
+            zero_hashes.push(current_zero_hash);
+        }
+
+        Ok(IncrementalMerkleTree {
+            frontier: zero_hashes.clone(),
+            next_index: 0,
+            depth,
+            zero_hashes,
+            marked: HashMap::new(),
+            hash_function,
+        })
+    }
+
+    /// Returns the number of appended leaves.
+    pub fn num_leaves(&self) -> usize {
+        self.next_index
+    }
+
+    fn is_full(&self) -> bool {
+        self.next_index >> self.depth != 0
+    }
+
+    /// Appends a leaf and returns its index.
+    pub fn append(&mut self, leaf: F) -> anyhow::Result<usize> {
+        if self.is_full() {
+            return Err(anyhow::anyhow!("Incremental Merkle tree is full"));
+        }
+
+        let index = self.next_index;
+        let mut node = leaf;
+        let mut node_index = index;
+        let mut level = 0;
+        while level < self.depth {
+            self.complete_witnesses(level, node_index, &node);
+            if node_index & 1 == 0 {
+                break;
+            }
+
+            node = hash_node(&self.hash_function, &self.frontier[level], &node)?;
+            node_index /= 2;
+            level += 1;
+        }
+        // either a left node waiting for its sibling, or the root of a full tree
+        self.frontier[level] = node;
+
+        self.next_index += 1;
+        Ok(index)
+    }
+
+    /// Appends a leaf and keeps its authentication path up to date from now on.
+    pub fn append_marked(&mut self, leaf: F) -> anyhow::Result<usize> {
+        if self.is_full() {
+            return Err(anyhow::anyhow!("Incremental Merkle tree is full"));
+        }
+
+        // left siblings are final already and wait in the frontier
+        let index = self.next_index;
+        let siblings = (0..self.depth)
+            .map(|level| {
+                if (index >> level) & 1 == 1 {
+                    Some(self.frontier[level])
+                } else {
+                    None
+                }
+            })
+            .collect();
+        self.marked.insert(
+            index,
+            Witness {
+                value: leaf,
+                siblings,
+            },
+        );
+
+        self.append(leaf)
+    }
+
+    /// Stops tracking the authentication path of a leaf. Returns whether it was marked.
+    pub fn unmark(&mut self, index: usize) -> bool {
+        self.marked.remove(&index).is_some()
+    }
+
+    /// Stores a freshly completed right node in the witnesses that need it.
+    fn complete_witnesses(&mut self, level: usize, node_index: usize, node: &F) {
+        if node_index & 1 == 0 {
+            return;
+        }
+        for (marked_index, witness) in self.marked.iter_mut() {
+            if (marked_index >> level) ^ 1 == node_index {
+                witness.siblings[level] = Some(*node);
+            }
+        }
+    }
+
+    /// Returns the Merkle root, hashing the frontier with empty subtrees in O(depth).
+    pub fn root(&self) -> anyhow::Result<F> {
+        if self.is_full() {
+            return Ok(self.frontier[self.depth]);
+        }
+
+        let mut node = self.zero_hashes[0];
+        for level in 0..self.depth {
+            node = if (self.next_index >> level) & 1 == 1 {
+                hash_node(&self.hash_function, &self.frontier[level], &node)?
+            } else {
+                hash_node(&self.hash_function, &node, &self.zero_hashes[level])?
+            };
+        }
+
+        Ok(node)
+    }
+
+    /// Generates a proof for a marked leaf against the current root.
+    pub fn get_proof(&self, index: usize) -> anyhow::Result<Proof<F>> {
+        let witness = self
+            .marked
+            .get(&index)
+            .ok_or_else(|| anyhow::anyhow!("Leaf {} is not marked", index))?;
+
+        // `node` is the root of the partially filled subtree holding the next leaf
+        let mut node = self.zero_hashes[0];
+        let mut siblings = Vec::with_capacity(self.depth);
+        for level in 0..self.depth {
+            let sibling = match witness.siblings[level] {
+                Some(sibling) => sibling,
+                None if (index >> level) ^ 1 == self.next_index >> level => node,
+                None => self.zero_hashes[level],
+            };
+            siblings.push(sibling);
+
+            if !self.is_full() {
+                node = if (self.next_index >> level) & 1 == 1 {
+                    hash_node(&self.hash_function, &self.frontier[level], &node)?
+                } else {
+                    hash_node(&self.hash_function, &node, &self.zero_hashes[level])?
+                };
+            }
+        }
+
+        Ok(Proof {
+            index,
+            value: witness.value,
+            siblings,
+            root: self.root()?,
+            empty: witness.value == self.hash_function.zero(),
+            arity: 2,
+        })
+    }
+}
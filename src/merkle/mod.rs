@@ -1,14 +1,141 @@
+use std::collections::VecDeque;
+
 use ark_ff::PrimeField;
 
-use crate::hash::HashFunction;
+use crate::hash::{HashFunction, NodeHash};
+
+pub mod encoding;
+pub mod incremental;
+pub mod multiproof;
+pub mod sparse;
 
+/// Inclusion proof for a node type `T`, a field element or a digest `[F; N]`.
 #[derive(Debug, Clone)]
-pub struct Proof<F: PrimeField> {
+pub struct Proof<T> {
     pub index: usize,
-    pub value: F,
-    pub siblings: Vec<F>,
-    pub root: F,
+    pub value: T,
+    pub siblings: Vec<T>, // `arity - 1` siblings per level, in child order
+    pub root: T,
     pub empty: bool,
+    pub arity: usize, // Number of children per node
+}
+
+impl<T: Copy + PartialEq> Proof<T> {
+    /// Recomputes the Merkle root from the leaf value and the sibling path.
+    /// Only `index`, `value` and `siblings` are used, so no tree instance is needed.
+    pub fn compute_root<H: NodeHash<T>>(&self, hash_function: &H) -> anyhow::Result<T> {
+        if !self.is_well_formed() {
+            return Err(anyhow::anyhow!("Malformed proof or index out of bounds"));
+        }
+
+        fold_path(
+            hash_function,
+            &self.value,
+            &self.siblings,
+            self.arity,
+            |level| child_position(self.index, self.arity, level),
+        )
+    }
+
+    /// Verifies the proof against a published root, e.g. one obtained by a light client.
+    /// The root carried inside the proof must match it as well.
+    pub fn verify<H: NodeHash<T>>(&self, root: &T, hash_function: &H) -> anyhow::Result<bool> {
+        if self.root != *root {
+            return Ok(false);
+        }
+        self.verify_trusted_root(hash_function)
+    }
+
+    /// Verifies the proof against the root it carries, which the caller has to trust.
+    /// Proofs whose `empty` flag disagrees with the leaf value are rejected.
+    pub fn verify_trusted_root<H: NodeHash<T>>(&self, hash_function: &H) -> anyhow::Result<bool> {
+        if !self.is_well_formed() || self.empty != (self.value == hash_function.zero_node()) {
+            return Ok(false);
+        }
+
+        Ok(self.compute_root(hash_function)? == self.root)
+    }
+
+    /// Returns the number of levels covered by the sibling path.
+    pub fn height(&self) -> Option<usize> {
+        if self.arity < 2 {
+            return None;
+        }
+        let height = self.siblings.len() / (self.arity - 1);
+        if height * (self.arity - 1) != self.siblings.len() {
+            return None;
+        }
+        Some(height)
+    }
+
+    /// Checks that the sibling path fits the arity and the index addresses one of its leaves.
+    fn is_well_formed(&self) -> bool {
+        match self.height() {
+            Some(height) => match self.arity.checked_pow(height as u32) {
+                Some(leafs) => self.index < leafs,
+                None => true,
+            },
+            None => false,
+        }
+    }
+}
+
+/// Returns the position of the ancestor of leaf `index` at `level` among its siblings.
+pub(crate) fn child_position(index: usize, arity: usize, level: usize) -> usize {
+    match arity.checked_pow(level as u32) {
+        Some(divisor) => (index / divisor) % arity,
+        None => 0,
+    }
+}
+
+/// Folds a leaf value up its sibling path and returns the resulting root.
+/// `position(level)` tells where the running node sits among its siblings at that level.
+pub(crate) fn fold_path<T: Copy, H: NodeHash<T>>(
+    hash_function: &H,
+    value: &T,
+    siblings: &[T],
+    arity: usize,
+    position: impl Fn(usize) -> usize,
+) -> anyhow::Result<T> {
+    let mut computed_hash = *value;
+    let mut children = Vec::with_capacity(arity);
+    for (level, level_siblings) in siblings.chunks(arity - 1).enumerate() {
+        let position = position(level);
+        children.clear();
+        children.extend_from_slice(&level_siblings[..position]);
+        children.push(computed_hash);
+        children.extend_from_slice(&level_siblings[position..]);
+
+        computed_hash = hash_function.hash_nodes(&children)?;
+    }
+
+    Ok(computed_hash)
+}
+
+/// Hashes two child nodes into their parent node.
+pub fn hash_node<F: PrimeField, H: HashFunction<F>>(
+    hash_function: &H,
+    left: &F,
+    right: &F,
+) -> anyhow::Result<F> {
+    hash_function.digest(left, right)
+}
+
+/// Hashes all child nodes of a node into their parent node.
+pub fn hash_children<F: PrimeField, H: HashFunction<F>>(
+    hash_function: &H,
+    children: &[F],
+) -> anyhow::Result<F> {
+    hash_function.digest_children(children)
+}
+
+/// Returns the smallest power of `base` which is not below `n`.
+pub fn next_pow(n: usize, base: usize) -> usize {
+    let mut pow = 1;
+    while pow < n {
+        pow *= base;
+    }
+    pow
 }
 
 /// Returns the next power of two for a given number if it is not already a power of two.
@@ -31,49 +158,69 @@ pub fn log2_pow2(n: usize) -> usize {
     n.trailing_zeros() as usize
 }
 
-pub struct MerkleTree<F: PrimeField, H: HashFunction<F>> {
-    pub data: Vec<F>,  // Stores hash values for all nodes
+/// Merkle tree over nodes of type `T`: single field elements for every `HashFunction`,
+/// or digests `[F; N]` of several elements on small fields.
+pub struct MerkleTree<T, H: NodeHash<T>> {
+    pub data: Vec<T>,  // Stores hash values for all nodes
     leafs: usize,      // Number of leaf nodes
     pub height: usize, // Height of the tree
+    pub arity: usize,  // Number of children per node
     #[allow(dead_code)]
-    zero_hashes: Vec<F>, // Stores precomputed hashes of zero nodes at each level
+    zero_hashes: Vec<T>, // Stores precomputed hashes of zero nodes at each level
     #[allow(dead_code)]
     min_index: usize, // Minimum index of used leaf nodes
     #[allow(dead_code)]
     max_index: usize, // Maximum index of used leaf nodes
+    root_history: VecDeque<T>, // Most recent roots, newest last
+    root_history_size: usize, // Number of roots to retain, 0 keeps only the current root
     hash_function: H,  // Hash function instance
 }
 
-impl<F: PrimeField, H: HashFunction<F>> MerkleTree<F, H> {
+impl<T: Copy + PartialEq, H: NodeHash<T>> MerkleTree<T, H> {
     /// Creates a new Merkle tree with a specified number of levels.
-    /// Initializes a tree with 2^n empty leaf nodes.
-    pub fn new_with_levels(n: usize, hash_function: H) -> anyhow::Result<MerkleTree<F, H>> {
+    /// Initializes a tree with arity^(n-1) empty leaf nodes, the arity being taken from the hash function.
+    pub fn new_with_levels(n: usize, hash_function: H) -> anyhow::Result<MerkleTree<T, H>> {
         // Calculate the number of leaf nodes based on the number of levels
-        let leafs = 1 << (n - 1); // 2^n leaf nodes
-                                  // Create a vector with empty leaf values
-        let leaf_values = vec![hash_function.zero(); leafs];
+        let leafs = hash_function
+            .node_arity()
+            .checked_pow(n as u32 - 1)
+            .ok_or_else(|| anyhow::anyhow!("Too many levels for Merkle tree"))?;
+        // Create a vector with empty leaf values
+        let leaf_values = vec![hash_function.zero_node(); leafs];
 
         // Call the original new method with the prepared leaf values
         MerkleTree::new(leaf_values, hash_function)
     }
 
     /// Creates a new fully computed Merkle tree with given leaf node values.
-    pub fn new(leaf_values: Vec<F>, hash_function: H) -> anyhow::Result<MerkleTree<F, H>> {
-        let leafs: usize = next_pow2(leaf_values.len());
-        let size: usize = 2 * leafs - 1;
-        let height: usize = log2_pow2(leafs);
+    /// Every node hashes `hash_function.node_arity()` children at once.
+    pub fn new(leaf_values: Vec<T>, hash_function: H) -> anyhow::Result<MerkleTree<T, H>> {
+        let arity = hash_function.node_arity();
+        if arity < 2 {
+            return Err(anyhow::anyhow!("Invalid arity for Merkle tree"));
+        }
+
+        let leafs: usize = next_pow(leaf_values.len(), arity);
+        let mut height: usize = 0;
+        let mut size: usize = leafs;
+        let mut level_size = leafs;
+        while level_size > 1 {
+            level_size /= arity;
+            size += level_size;
+            height += 1;
+        }
 
         // compute zeros
-        let mut current_zero_hash = hash_function.zero();
+        let mut current_zero_hash = hash_function.zero_node();
         let mut zero_hashes = Vec::with_capacity(height);
         for _ in 0..height {
             zero_hashes.push(current_zero_hash);
-            current_zero_hash = hash_function.hash(&current_zero_hash, &current_zero_hash)?[1];
+            current_zero_hash = hash_function.hash_nodes(&vec![current_zero_hash; arity])?;
         }
 
         // calculate merkle tree
         let mut data = leaf_values.clone();
-        data.resize(size, hash_function.zero());
+        data.resize(size, hash_function.zero_node());
 
         let mut current_level = 0;
         let mut current_level_size = leafs;
@@ -82,19 +229,16 @@ impl<F: PrimeField, H: HashFunction<F>> MerkleTree<F, H> {
         while current_level < height {
             let mut i = 0;
             while i < current_level_size {
-                let left_index = level_leafs_accumulated + i;
-                let right_index = left_index + 1;
-
-                let left = data[left_index];
-                let right = data[right_index];
+                let first_index = level_leafs_accumulated + i;
+                let children = &data[first_index..first_index + arity];
 
-                data[level_leafs_accumulated + current_level_size + i / 2] =
-                    hash_function.hash(&left, &right)?[1];
-                i += 2;
+                data[level_leafs_accumulated + current_level_size + i / arity] =
+                    hash_function.hash_nodes(children)?;
+                i += arity;
             }
 
             level_leafs_accumulated += current_level_size;
-            current_level_size /= 2;
+            current_level_size /= arity;
             current_level += 1;
         }
 
@@ -102,16 +246,53 @@ impl<F: PrimeField, H: HashFunction<F>> MerkleTree<F, H> {
             data,
             leafs,
             height,
+            arity,
             zero_hashes,
             min_index: usize::MAX,
             max_index: 0,
+            root_history: VecDeque::new(),
+            root_history_size: 0,
             hash_function,
         };
 
         Ok(mt)
     }
 
+    /// Retains the last `size` roots, so that proofs against slightly stale roots are
+    /// still accepted by `prove_with_history`. The current root counts as one of them.
+    pub fn with_root_history(mut self, size: usize) -> Self {
+        self.root_history_size = size;
+        self.root_history.clear();
+        self.record_root();
+        self
+    }
+
+    /// Returns the retained roots, oldest first.
+    pub fn root_history(&self) -> &VecDeque<T> {
+        &self.root_history
+    }
+
+    /// Checks whether `root` is the current root or one of the retained ones.
+    pub fn is_known_root(&self, root: &T) -> bool {
+        *root == self.root() || self.root_history.contains(root)
+    }
+
+    fn record_root(&mut self) {
+        if self.root_history_size == 0 {
+            return;
+        }
+
+        let root = self.root();
+        if self.root_history.back() != Some(&root) {
+            self.root_history.push_back(root);
+        }
+        while self.root_history.len() > self.root_history_size {
+            self.root_history.pop_front();
+        }
+    }
+
     /// computes siblings and parent nodes index
+    /// `arity - 1` siblings are returned per level, in child order
     pub fn compute_indices(&self, index: usize) -> (Vec<usize>, Vec<usize>) {
         let mut level = 0;
         let mut level_leafs = self.leafs;
@@ -122,12 +303,16 @@ impl<F: PrimeField, H: HashFunction<F>> MerkleTree<F, H> {
         let mut index = index;
 
         while level < self.height {
-            let sibling_index = if index % 2 == 0 { index + 1 } else { index - 1 };
-            siblings.push(sibling_index + level_leafs_accumulated);
+            let first_index = index - index % self.arity;
+            for sibling_index in first_index..first_index + self.arity {
+                if sibling_index != index {
+                    siblings.push(sibling_index + level_leafs_accumulated);
+                }
+            }
 
             level_leafs_accumulated += level_leafs;
-            level_leafs /= 2;
-            let parent_index = index / 2;
+            level_leafs /= self.arity;
+            let parent_index = index / self.arity;
             index = parent_index;
             parents.push(parent_index + level_leafs_accumulated);
 
@@ -137,34 +322,53 @@ impl<F: PrimeField, H: HashFunction<F>> MerkleTree<F, H> {
         (siblings, parents)
     }
 
-    pub fn insert_leaf(&mut self, index: usize, value: F) -> anyhow::Result<()> {
+    pub fn insert_leaf(&mut self, index: usize, value: T) -> anyhow::Result<()> {
         if index >= self.leafs {
             return Err(anyhow::anyhow!("Index out of bounds"));
         }
 
-        self.data[index] = value;
-        let (siblings, parents) = self.compute_indices(index);
+        self.update_leaves(&[(index, value)])?;
+        Ok(())
+    }
 
-        let mut value = value;
-        for (sib_idx, par_idx) in siblings.iter().zip(parents.iter()) {
-            let sibling_index = *sib_idx;
-            let parent_index = *par_idx;
+    /// Writes several leaves and recomputes every affected internal node exactly once,
+    /// level by level. Later entries win for repeated indices. Returns the new root.
+    pub fn update_leaves(&mut self, leaves: &[(usize, T)]) -> anyhow::Result<T> {
+        if leaves.iter().any(|(index, _)| *index >= self.leafs) {
+            return Err(anyhow::anyhow!("Index out of bounds"));
+        }
 
-            let sibling = self.data[sibling_index];
-            value = if sibling_index % 2 == 0 {
-                self._hash(&sibling, &value)?
-            } else {
-                self._hash(&value, &sibling)?
-            };
+        let mut dirty = Vec::with_capacity(leaves.len());
+        for (index, value) in leaves {
+            self.data[*index] = *value;
+            dirty.push(*index);
+        }
+        dirty.sort_unstable();
+
+        let mut offset = 0;
+        let mut level_size = self.leafs;
+        for _ in 0..self.height {
+            let parent_offset = offset + level_size;
+
+            let mut parents: Vec<usize> = dirty.iter().map(|index| index / self.arity).collect();
+            parents.dedup();
+            for parent in parents.iter() {
+                let first_index = offset + self.arity * parent;
+                let children = &self.data[first_index..first_index + self.arity];
+                self.data[parent_offset + parent] = self.hash_function.hash_nodes(children)?;
+            }
 
-            self.data[parent_index] = value;
+            dirty = parents;
+            offset = parent_offset;
+            level_size /= self.arity;
         }
 
-        Ok(())
+        self.record_root();
+        Ok(self.root())
     }
 
     /// Generates a proof for a leaf node.
-    pub fn get_proof(&self, index: usize) -> anyhow::Result<Proof<F>> {
+    pub fn get_proof(&self, index: usize) -> anyhow::Result<Proof<T>> {
         if index >= self.leafs {
             return Err(anyhow::anyhow!("Index out of bounds")); // Check if the index is within the bounds
         }
@@ -172,7 +376,7 @@ impl<F: PrimeField, H: HashFunction<F>> MerkleTree<F, H> {
         let leaf_value = self.data[index]; // Clone the leaf value
         let (siblings, _) = self.compute_indices(index);
 
-        let path: Vec<F> = siblings
+        let path: Vec<T> = siblings
             .iter()
             .map(|sibling_index| self.data[*sibling_index])
             .collect();
@@ -183,35 +387,52 @@ impl<F: PrimeField, H: HashFunction<F>> MerkleTree<F, H> {
             value: leaf_value,
             siblings: path,
             root: root_value,
-            empty: leaf_value == self.hash_function.zero(), // Check if the leaf value is the same as zero value
+            empty: leaf_value == self.hash_function.zero_node(), // Check if the leaf value is the same as zero value
+            arity: self.arity,
         })
     }
 
-    /// Verifies a proof.
-    pub fn prove(&self, proof: Proof<F>) -> anyhow::Result<bool> {
-        let mut computed_hash = proof.value;
-        let (siblings, _) = self.compute_indices(proof.index);
+    /// Verifies a proof against the current root of this tree.
+    pub fn prove(&self, proof: Proof<T>) -> anyhow::Result<bool> {
+        if !self.fits_shape(&proof) {
+            return Ok(false);
+        }
 
-        for (sibidx, sib) in siblings.into_iter().enumerate() {
-            if sib % 2 == 0 {
-                computed_hash = self._hash(&proof.siblings[sibidx], &computed_hash)?;
-            } else {
-                computed_hash = self._hash(&computed_hash, &proof.siblings[sibidx])?;
-            }
+        proof.verify(&self.root(), &self.hash_function)
+    }
+
+    /// Verifies a proof against the current root or any root in the history window.
+    pub fn prove_with_history(&self, proof: Proof<T>) -> anyhow::Result<bool> {
+        if !self.fits_shape(&proof) || !self.is_known_root(&proof.root) {
+            return Ok(false);
         }
 
-        Ok(computed_hash == proof.root
-            && proof.root == self.root()
-            && proof.siblings.len() == self.height)
+        proof.verify_trusted_root(&self.hash_function)
     }
 
-    /// Returns the Merkle root.
-    pub fn root(&self) -> F {
-        *self.data.last().unwrap()
+    /// Checks that a proof was generated for a tree of this arity and height.
+    fn fits_shape(&self, proof: &Proof<T>) -> bool {
+        proof.arity == self.arity && proof.height() == Some(self.height)
+    }
+
+    /// Returns the position of the first node of `level` inside `data`.
+    fn level_offset(&self, level: usize) -> usize {
+        let mut offset = 0;
+        let mut level_size = self.leafs;
+        for _ in 0..level {
+            offset += level_size;
+            level_size /= self.arity;
+        }
+        offset
     }
 
-    fn _hash(&self, a: &F, b: &F) -> anyhow::Result<F> {
-        let out = self.hash_function.hash(a, b)?;
-        Ok(out[1])
+    /// Returns the hash function the tree was built with.
+    pub fn hash_function(&self) -> &H {
+        &self.hash_function
+    }
+
+    /// Returns the Merkle root.
+    pub fn root(&self) -> T {
+        *self.data.last().unwrap()
     }
 }
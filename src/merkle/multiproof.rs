@@ -0,0 +1,157 @@
+use ark_ff::PrimeField;
+
+use super::{hash_children, MerkleTree};
+use crate::hash::HashFunction;
+
+/// Proof for several leaves at once. Nodes that can be recomputed from the proven
+/// leaves are left out, so shared ancestors are only paid for once.
+#[derive(Debug, Clone)]
+pub struct MultiProof<F: PrimeField> {
+    pub indices: Vec<usize>, // Proven leaf indices, strictly increasing
+    pub values: Vec<F>,      // Leaf values in the order of `indices`
+    pub siblings: Vec<F>,    // Missing nodes, level by level from left to right
+    pub height: usize,       // Height of the tree
+    pub arity: usize,        // Number of children per node
+    pub root: F,
+}
+
+impl<F: PrimeField> MultiProof<F> {
+    /// Recomputes the Merkle root from the leaf values and the decommitted nodes.
+    pub fn compute_root<H: HashFunction<F>>(&self, hash_function: &H) -> anyhow::Result<F> {
+        if self.indices.is_empty() || self.indices.len() != self.values.len() {
+            return Err(anyhow::anyhow!("Invalid number of leaves in multi-proof"));
+        }
+        if self.indices.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(anyhow::anyhow!(
+                "Multi-proof indices are not strictly increasing"
+            ));
+        }
+        if self.arity < 2 {
+            return Err(anyhow::anyhow!("Invalid arity in multi-proof"));
+        }
+        let last = self.indices[self.indices.len() - 1];
+        if let Some(leafs) = self.arity.checked_pow(self.height as u32) {
+            if last >= leafs {
+                return Err(anyhow::anyhow!("Index out of bounds"));
+            }
+        }
+
+        let mut nodes: Vec<(usize, F)> = self
+            .indices
+            .iter()
+            .copied()
+            .zip(self.values.iter().copied())
+            .collect();
+        let mut siblings = self.siblings.iter();
+        let mut next_sibling = || {
+            siblings
+                .next()
+                .copied()
+                .ok_or_else(|| anyhow::anyhow!("Multi-proof is missing sibling nodes"))
+        };
+
+        let mut children = Vec::with_capacity(self.arity);
+        for _ in 0..self.height {
+            let mut parents = Vec::with_capacity(nodes.len());
+            let mut i = 0;
+            while i < nodes.len() {
+                let parent = nodes[i].0 / self.arity;
+                children.clear();
+                for child in parent * self.arity..(parent + 1) * self.arity {
+                    if i < nodes.len() && nodes[i].0 == child {
+                        children.push(nodes[i].1);
+                        i += 1;
+                    } else {
+                        children.push(next_sibling()?);
+                    }
+                }
+
+                parents.push((parent, hash_children(hash_function, &children)?));
+            }
+            nodes = parents;
+        }
+
+        if siblings.next().is_some() {
+            return Err(anyhow::anyhow!("Multi-proof has unused sibling nodes"));
+        }
+
+        Ok(nodes[0].1)
+    }
+
+    /// Verifies the proof against a published root.
+    /// The root carried inside the proof must match it as well.
+    pub fn verify<H: HashFunction<F>>(&self, root: &F, hash_function: &H) -> anyhow::Result<bool> {
+        if self.root != *root {
+            return Ok(false);
+        }
+        self.verify_trusted_root(hash_function)
+    }
+
+    /// Verifies the proof against the root it carries, which the caller has to trust.
+    pub fn verify_trusted_root<H: HashFunction<F>>(
+        &self,
+        hash_function: &H,
+    ) -> anyhow::Result<bool> {
+        match self.compute_root(hash_function) {
+            Ok(root) => Ok(root == self.root),
+            Err(_) => Ok(false),
+        }
+    }
+}
+
+impl<F: PrimeField, H: HashFunction<F>> MerkleTree<F, H> {
+    /// Generates a single proof for a set of leaf nodes.
+    pub fn get_multi_proof(&self, indices: &[usize]) -> anyhow::Result<MultiProof<F>> {
+        if indices.is_empty() {
+            return Err(anyhow::anyhow!("No leaf index given for multi-proof"));
+        }
+        if indices.iter().any(|index| *index >= self.leafs) {
+            return Err(anyhow::anyhow!("Index out of bounds"));
+        }
+
+        let mut known = indices.to_vec();
+        known.sort_unstable();
+        known.dedup();
+
+        let values = known.iter().map(|index| self.data[*index]).collect();
+        let proven = known.clone();
+
+        let mut siblings = vec![];
+        for level in 0..self.height {
+            let offset = self.level_offset(level);
+            let mut parents = Vec::with_capacity(known.len());
+            let mut i = 0;
+            while i < known.len() {
+                let parent = known[i] / self.arity;
+                for child in parent * self.arity..(parent + 1) * self.arity {
+                    if i < known.len() && known[i] == child {
+                        i += 1;
+                    } else {
+                        siblings.push(self.data[offset + child]);
+                    }
+                }
+
+                parents.push(parent);
+            }
+            known = parents;
+        }
+
+        Ok(MultiProof {
+            indices: proven,
+            values,
+            siblings,
+            height: self.height,
+            arity: self.arity,
+            root: self.root(),
+        })
+    }
+
+    /// Verifies a multi-proof against the current root of this tree.
+    pub fn prove_multi(&self, proof: MultiProof<F>) -> anyhow::Result<bool> {
+        if proof.height != self.height || proof.arity != self.arity {
+            return Ok(false);
+        }
+
+        proof.verify(&self.root(), &self.hash_function)
+    }
+}
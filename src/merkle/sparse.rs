@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+
+use ark_ff::{BigInteger, BigInteger256, PrimeField};
+
+use super::{fold_path, hash_node};
+use crate::hash::HashFunction;
+
+/// Leaf position in a sparse tree, wide enough for trees of depth 256.
+pub type SparseIndex = BigInteger256;
+
+/// Maximum supported depth of a sparse Merkle tree.
+pub const MAX_SPARSE_DEPTH: usize = 256;
+
+#[derive(Debug, Clone)]
+pub struct SparseProof<F: PrimeField> {
+    pub index: SparseIndex,
+    pub value: F,
+    pub siblings: Vec<F>,
+    pub root: F,
+    pub empty: bool,
+}
+
+impl<F: PrimeField> SparseProof<F> {
+    /// Recomputes the Merkle root from the leaf value and the sibling path.
+    pub fn compute_root<H: HashFunction<F>>(&self, hash_function: &H) -> anyhow::Result<F> {
+        if !index_in_range(&self.index, self.siblings.len()) {
+            return Err(anyhow::anyhow!("Index out of bounds"));
+        }
+
+        fold_path(hash_function, &self.value, &self.siblings, 2, |level| {
+            self.index.get_bit(level) as usize
+        })
+    }
+
+    /// Verifies the proof against a published root.
+    /// The root carried inside the proof must match it as well.
+    pub fn verify<H: HashFunction<F>>(&self, root: &F, hash_function: &H) -> anyhow::Result<bool> {
+        if self.root != *root {
+            return Ok(false);
+        }
+        self.verify_trusted_root(hash_function)
+    }
+
+    /// Verifies the proof against the root it carries, which the caller has to trust.
+    /// Proofs whose `empty` flag disagrees with the leaf value are rejected.
+    pub fn verify_trusted_root<H: HashFunction<F>>(
+        &self,
+        hash_function: &H,
+    ) -> anyhow::Result<bool> {
+        if !index_in_range(&self.index, self.siblings.len())
+            || self.empty != (self.value == hash_function.zero())
+        {
+            return Ok(false);
+        }
+
+        Ok(self.compute_root(hash_function)? == self.root)
+    }
+
+    /// Verifies that the proven leaf is empty under the published root.
+    pub fn verify_exclusion<H: HashFunction<F>>(
+        &self,
+        root: &F,
+        hash_function: &H,
+    ) -> anyhow::Result<bool> {
+        if !self.empty {
+            return Ok(false);
+        }
+        self.verify(root, hash_function)
+    }
+
+    /// Converts a proof of an empty leaf into an exclusion proof.
+    pub fn into_exclusion<H: HashFunction<F>>(
+        self,
+        hash_function: &H,
+    ) -> anyhow::Result<ExclusionProof<F>> {
+        if !self.empty || self.value != hash_function.zero() {
+            return Err(anyhow::anyhow!("Leaf is not empty"));
+        }
+
+        Ok(ExclusionProof {
+            index: self.index,
+            siblings: self.siblings,
+            root: self.root,
+        })
+    }
+}
+
+/// Proof that a leaf position holds the zero value, i.e. that a key is not in the tree.
+/// The leaf value is implied, so a verifier always starts from the empty leaf.
+#[derive(Debug, Clone)]
+pub struct ExclusionProof<F: PrimeField> {
+    pub index: SparseIndex,
+    pub siblings: Vec<F>,
+    pub root: F,
+}
+
+impl<F: PrimeField> ExclusionProof<F> {
+    /// Recomputes the Merkle root from an empty leaf and the sibling path.
+    pub fn compute_root<H: HashFunction<F>>(&self, hash_function: &H) -> anyhow::Result<F> {
+        if !index_in_range(&self.index, self.siblings.len()) {
+            return Err(anyhow::anyhow!("Index out of bounds"));
+        }
+
+        fold_path(
+            hash_function,
+            &hash_function.zero(),
+            &self.siblings,
+            2,
+            |level| self.index.get_bit(level) as usize,
+        )
+    }
+
+    /// Verifies the proof against a published root.
+    /// The root carried inside the proof must match it as well.
+    pub fn verify<H: HashFunction<F>>(&self, root: &F, hash_function: &H) -> anyhow::Result<bool> {
+        if self.root != *root {
+            return Ok(false);
+        }
+        self.verify_trusted_root(hash_function)
+    }
+
+    /// Verifies the proof against the root it carries, which the caller has to trust.
+    pub fn verify_trusted_root<H: HashFunction<F>>(
+        &self,
+        hash_function: &H,
+    ) -> anyhow::Result<bool> {
+        if !index_in_range(&self.index, self.siblings.len()) {
+            return Ok(false);
+        }
+
+        Ok(self.compute_root(hash_function)? == self.root)
+    }
+}
+
+/// Checks that `index` addresses a leaf of a tree with `depth` levels.
+fn index_in_range(index: &SparseIndex, depth: usize) -> bool {
+    index.num_bits() as usize <= depth
+}
+
+/// Returns the index of the other child of the same parent.
+fn sibling_index(index: &SparseIndex) -> SparseIndex {
+    let mut sibling = *index;
+    if index.is_odd() {
+        sibling.sub_with_borrow(&SparseIndex::from(1u64));
+    } else {
+        sibling.add_with_carry(&SparseIndex::from(1u64));
+    }
+    sibling
+}
+
+/// Sparse Merkle tree which only stores nodes that differ from the empty subtree
+/// of their level, so memory grows with the number of populated leaves.
+pub struct SparseMerkleTree<F: PrimeField, H: HashFunction<F>> {
+    nodes: HashMap<(usize, SparseIndex), F>, // Non-default nodes keyed by (level, index)
+    pub depth: usize,                        // Number of levels below the root
+    zero_hashes: Vec<F>, // Hash of an empty subtree for every level, root included
+    hash_function: H,    // Hash function instance
+}
+
+impl<F: PrimeField, H: HashFunction<F>> SparseMerkleTree<F, H> {
+    /// Creates an empty sparse Merkle tree with 2^depth leaf positions.
+    pub fn new(depth: usize, hash_function: H) -> anyhow::Result<SparseMerkleTree<F, H>> {
+        if depth == 0 || depth > MAX_SPARSE_DEPTH {
+            return Err(anyhow::anyhow!(
+                "Invalid depth for sparse Merkle tree, expected 1..={}",
+                MAX_SPARSE_DEPTH
+            ));
+        }
+        if hash_function.arity() != 2 {
+            return Err(anyhow::anyhow!(
+                "Sparse Merkle tree requires a binary hash function"
+            ));
+        }
+
+        // compute zeros
+        let mut current_zero_hash = hash_function.zero();
+        let mut zero_hashes = Vec::with_capacity(depth + 1);
+        zero_hashes.push(current_zero_hash);
+        for _ in 0..depth {
+            current_zero_hash = hash_node(&hash_function, &current_zero_hash, &current_zero_hash)?;
+            zero_hashes.push(current_zero_hash);
+        }
+
+        Ok(SparseMerkleTree {
+            nodes: HashMap::new(),
+            depth,
+            zero_hashes,
+            hash_function,
+        })
+    }
+
+    /// Returns the node at `level` (0 being the leaves), falling back to the empty subtree hash.
+    pub fn get_node(&self, level: usize, index: &SparseIndex) -> F {
+        match self.nodes.get(&(level, *index)) {
+            Some(node) => *node,
+            None => self.zero_hashes[level],
+        }
+    }
+
+    /// Returns the value stored at a leaf.
+    pub fn get_leaf(&self, index: &SparseIndex) -> F {
+        self.get_node(0, index)
+    }
+
+    /// Returns the number of stored (non-default) nodes.
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn set_node(&mut self, level: usize, index: SparseIndex, value: F) {
+        if value == self.zero_hashes[level] {
+            self.nodes.remove(&(level, index));
+        } else {
+            self.nodes.insert((level, index), value);
+        }
+    }
+
+    pub fn insert_leaf(&mut self, index: SparseIndex, value: F) -> anyhow::Result<()> {
+        if !index_in_range(&index, self.depth) {
+            return Err(anyhow::anyhow!("Index out of bounds"));
+        }
+
+        self.set_node(0, index, value);
+
+        let mut index = index;
+        let mut value = value;
+        for level in 0..self.depth {
+            let sibling = self.get_node(level, &sibling_index(&index));
+            value = if index.is_odd() {
+                hash_node(&self.hash_function, &sibling, &value)?
+            } else {
+                hash_node(&self.hash_function, &value, &sibling)?
+            };
+
+            index.div2();
+            self.set_node(level + 1, index, value);
+        }
+
+        Ok(())
+    }
+
+    /// Generates a proof for a leaf node, which may be empty.
+    pub fn get_proof(&self, index: &SparseIndex) -> anyhow::Result<SparseProof<F>> {
+        if !index_in_range(index, self.depth) {
+            return Err(anyhow::anyhow!("Index out of bounds"));
+        }
+
+        let leaf_value = self.get_leaf(index);
+
+        let mut siblings = Vec::with_capacity(self.depth);
+        let mut current = *index;
+        for level in 0..self.depth {
+            siblings.push(self.get_node(level, &sibling_index(&current)));
+            current.div2();
+        }
+
+        Ok(SparseProof {
+            index: *index,
+            value: leaf_value,
+            siblings,
+            root: self.root(),
+            empty: leaf_value == self.hash_function.zero(),
+        })
+    }
+
+    /// Verifies a proof against the current root of this tree.
+    pub fn prove(&self, proof: SparseProof<F>) -> anyhow::Result<bool> {
+        if proof.siblings.len() != self.depth {
+            return Ok(false);
+        }
+
+        proof.verify(&self.root(), &self.hash_function)
+    }
+
+    /// Generates a proof that a leaf position is empty.
+    pub fn get_exclusion_proof(&self, index: &SparseIndex) -> anyhow::Result<ExclusionProof<F>> {
+        self.get_proof(index)?.into_exclusion(&self.hash_function)
+    }
+
+    /// Verifies an exclusion proof against the current root of this tree.
+    pub fn prove_exclusion(&self, proof: ExclusionProof<F>) -> anyhow::Result<bool> {
+        if proof.siblings.len() != self.depth {
+            return Ok(false);
+        }
+
+        proof.verify(&self.root(), &self.hash_function)
+    }
+
+    /// Returns the Merkle root.
+    pub fn root(&self) -> F {
+        self.get_node(self.depth, &SparseIndex::from(0u64))
+    }
+}